@@ -0,0 +1,337 @@
+// Music and sound effects. What to play is worked out by watching the
+// Simulation from frame to frame (see Cues), so the simulation itself stays
+// silent. The music comes from the synthesizer in synth.rs. Without an audio
+// device, or with --mute, the null backend is used and nothing plays.
+use sdl2::mixer::{self, Channel, Chunk, LoaderRWops};
+use sdl2::rwops::RWops;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::sdl::{self, MusicHook, Subsystem};
+use crate::simulation::*;
+use crate::synth::{self, Mood, Synth};
+
+pub static VOLUME_PATH: &str = "volume.toml";
+
+// A stat dropping below LOW plays StatLow; it has to climb back over
+// RECOVERED before it can again.
+static STAT_LOW: f32 = 0.25;
+static STAT_RECOVERED: f32 = 0.5;
+static WATCHED_STATS: [Stat; 4] = [Stat::Belonging, Stat::Purpose, Stat::Pride, Stat::Relaxation];
+static SAMPLE_RATE: i32 = 22050;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Sound {
+    Focus,
+    ModalOpen,
+    Confirm,
+    Takeoff,
+    Landing,
+    StatLow,
+    StatRecovered,
+}
+
+// In the order the files are loaded.
+static SOUNDS: [Sound; 7] = [
+    Sound::Focus,
+    Sound::ModalOpen,
+    Sound::Confirm,
+    Sound::Takeoff,
+    Sound::Landing,
+    Sound::StatLow,
+    Sound::StatRecovered,
+];
+
+static SOUND_FILES: [&[u8]; 7] = [
+    include_bytes!("../assets/audio/focus.wav"),
+    include_bytes!("../assets/audio/modal_open.wav"),
+    include_bytes!("../assets/audio/confirm.wav"),
+    include_bytes!("../assets/audio/takeoff.wav"),
+    include_bytes!("../assets/audio/landing.wav"),
+    include_bytes!("../assets/audio/stat_low.wav"),
+    include_bytes!("../assets/audio/stat_recovered.wav"),
+];
+
+// What the music should be; the title gets the mood of a life just begun,
+// and nothing plays once the life is over.
+pub fn music(sim: &Simulation) -> Option<Mood> {
+    match sim.game_state {
+        GameState::Title | GameState::Instruction => Some(synth::mood(&Stats::new(), 0)),
+        GameState::GameOver => None,
+        _ => Some(synth::mood(&sim.stats, focus_stage(sim))),
+    }
+}
+
+// The parts of the last frame's Simulation that sounds are played for.
+pub struct Cues {
+    focus: Focus,
+    modal: bool,
+    flying: bool,
+    low: [bool; 4], // for each of WATCHED_STATS
+}
+
+impl Cues {
+    pub fn new(sim: &Simulation) -> Self {
+        let mut low = [false; 4];
+        for (i, &stat) in WATCHED_STATS.iter().enumerate() {
+            low[i] = sim.stats.get(stat) < STAT_LOW;
+        }
+        Self {
+            focus: sim.current_focus,
+            modal: is_modal(sim),
+            flying: is_flying(sim),
+            low: low,
+        }
+    }
+
+    // The sounds for whatever changed since the last call.
+    pub fn update(&mut self, sim: &Simulation) -> Vec<Sound> {
+        let mut sounds = vec![];
+        if sim.current_focus != self.focus {
+            sounds.push(Sound::Focus);
+        }
+        match (self.modal, is_modal(sim)) {
+            (false, true) => sounds.push(Sound::ModalOpen),
+            (true, false) => sounds.push(Sound::Confirm),
+            _ => {}
+        }
+        match (self.flying, is_flying(sim)) {
+            (false, true) => sounds.push(Sound::Takeoff),
+            (true, false) => sounds.push(Sound::Landing),
+            _ => {}
+        }
+        for (i, &stat) in WATCHED_STATS.iter().enumerate() {
+            let value = sim.stats.get(stat);
+            if !self.low[i] && value < STAT_LOW {
+                self.low[i] = true;
+                sounds.push(Sound::StatLow);
+            } else if self.low[i] && value > STAT_RECOVERED {
+                self.low[i] = false;
+                sounds.push(Sound::StatRecovered);
+            }
+        }
+        self.focus = sim.current_focus;
+        self.modal = is_modal(sim);
+        self.flying = is_flying(sim);
+        sounds
+    }
+}
+
+fn is_modal(sim: &Simulation) -> bool {
+    matches!(sim.game_state, GameState::Modal)
+}
+
+fn is_flying(sim: &Simulation) -> bool {
+    matches!(sim.game_state, GameState::Fly)
+}
+
+// Each from 0 (off) to 1.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Volume {
+    pub music: f32,
+    pub sound: f32,
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self { music: 0.75, sound: 1.0 }
+    }
+}
+
+// The next volume down, going back to full after off.
+pub fn step_down(v: f32) -> f32 {
+    if v <= 0.0 { 1.0 } else { (v - 0.25).max(0.0) }
+}
+
+// The player's volume.toml, or the defaults if there isn't one.
+pub fn load_volume(path: &Path) -> Result<Volume, String> {
+    if !path.exists() {
+        return Ok(Volume::default());
+    }
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("audio: {}: {}", path.display(), e))?;
+    toml::from_str(&src).map_err(|e| format!("audio: {}: {}", path.display(), e))
+}
+
+pub fn write_volume(v: &Volume, path: &Path) -> Result<(), String> {
+    let src = toml::to_string(v).map_err(|e| format!("audio: {}", e))?;
+    std::fs::write(path, src).map_err(|e| format!("audio: {}: {}", path.display(), e))
+}
+
+enum Backend {
+    Null,
+    // the synth is shared with the music hook, on the audio thread
+    Mixer { sounds: Vec<Chunk>, synth: Arc<Mutex<Synth>>, _hook: MusicHook },
+}
+
+pub struct Audio {
+    backend: Backend,
+    volume: Volume,
+    cues: Option<Cues>,
+    paused: bool,
+}
+
+impl Audio {
+    // Plays nothing, but otherwise behaves like a real device.
+    pub fn null(volume: Volume) -> Self {
+        Self { backend: Backend::Null, volume: volume, cues: None, paused: false }
+    }
+
+    // The mixer, or the null backend if it can't be opened.
+    pub fn open(volume: Volume) -> Self {
+        match open_mixer() {
+            Ok(backend) => {
+                let mut audio = Self::null(volume);
+                audio.backend = backend;
+                audio.set_volume(volume);
+                audio
+            }
+            Err(e) => {
+                eprintln!("audio: {}; playing without sound", e);
+                Self::null(volume)
+            }
+        }
+    }
+
+    pub fn volume(&self) -> Volume {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: Volume) {
+        self.volume = volume;
+        if let Backend::Mixer { sounds, synth, .. } = &mut self.backend {
+            let sound = (volume.sound * mixer::MAX_VOLUME as f32) as i32;
+            for chunk in sounds.iter_mut() {
+                chunk.set_volume(sound);
+            }
+            synth.lock().unwrap().volume = if self.paused { 0.0 } else { volume.music };
+        }
+    }
+
+    // Start over watching sim, without playing anything for how it differs
+    // from the last one (a loaded life, say).
+    pub fn reset(&mut self, sim: &Simulation) {
+        self.cues = Some(Cues::new(sim));
+    }
+
+    // Call once a frame, after the simulation has been stepped.
+    pub fn update(&mut self, sim: &Simulation, paused: bool) {
+        let sounds = match self.cues.as_mut() {
+            Some(cues) => cues.update(sim),
+            None => {
+                self.cues = Some(Cues::new(sim));
+                vec![]
+            }
+        };
+        if let Backend::Mixer { sounds: chunks, synth, .. } = &self.backend {
+            for sound in sounds.iter() {
+                let i = SOUNDS.iter().position(|s| s == sound).unwrap();
+                // all channels busy is fine; the sound is skipped
+                let _ = Channel::all().play(&chunks[i], 0);
+            }
+            synth.lock().unwrap().set_mood(music(sim));
+        }
+        if paused != self.paused {
+            self.paused = paused;
+            let volume = self.volume;
+            self.set_volume(volume);
+        }
+    }
+}
+
+fn open_mixer() -> Result<Backend, String> {
+    sdl::init(Subsystem::Audio).map_err(|e| format!("no audio device ({})", e))?;
+    mixer::open_audio(SAMPLE_RATE, mixer::AUDIO_S16SYS, 1, 1024)?;
+    // the device may not have given us the rate or channels asked for
+    let (rate, format, channels) = mixer::query_spec()?;
+    if format != mixer::AUDIO_S16SYS {
+        mixer::close_audio();
+        return Err("the device doesn't take 16-bit samples".to_string());
+    }
+    let mut sounds = vec![];
+    for bytes in SOUND_FILES.iter() {
+        sounds.push(RWops::from_bytes(bytes)?.load_wav()?);
+    }
+    let synth = Arc::new(Mutex::new(Synth::new(rate as u32)));
+    let playing = synth.clone();
+    let channels = channels.max(1) as usize;
+    // 16-bit samples, interleaved, with every channel the same
+    let hook = sdl::hook_music(move |stream| {
+        let mut synth = playing.lock().unwrap();
+        let mut samples = [0i16; 256];
+        for block in stream.chunks_mut(2 * channels * samples.len()) {
+            let n = block.len() / (2 * channels);
+            synth.render(&mut samples[..n]);
+            for (frame, sample) in block.chunks_mut(2 * channels).zip(samples.iter()) {
+                let bytes = sample.to_ne_bytes();
+                for s in frame.chunks_mut(2) {
+                    s.copy_from_slice(&bytes[..s.len()]);
+                }
+            }
+        }
+    });
+    Ok(Backend::Mixer { sounds: sounds, synth: synth, _hook: hook })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::tests::{live, sim};
+
+    #[test]
+    fn cues_follow_the_simulation() {
+        let mut sim = sim(1);
+        let mut cues = Cues::new(&sim);
+        assert!(cues.update(&sim).is_empty());
+        sim.current_focus = Focus::Socialize;
+        assert!(cues.update(&sim) == [Sound::Focus]);
+        sim.game_state = GameState::Modal;
+        assert!(cues.update(&sim) == [Sound::ModalOpen]);
+        sim.game_state = GameState::Game;
+        assert!(cues.update(&sim) == [Sound::Confirm]);
+        sim.game_state = GameState::Fly;
+        assert!(cues.update(&sim) == [Sound::Takeoff]);
+        sim.game_state = GameState::Game;
+        assert!(cues.update(&sim) == [Sound::Landing]);
+        // low once, until it has properly recovered
+        sim.stats.pride = STAT_LOW - 0.1;
+        assert!(cues.update(&sim) == [Sound::StatLow]);
+        sim.stats.pride = STAT_RECOVERED - 0.1;
+        assert!(cues.update(&sim).is_empty());
+        sim.stats.pride = STAT_LOW - 0.1;
+        assert!(cues.update(&sim).is_empty());
+        sim.stats.pride = STAT_RECOVERED + 0.1;
+        assert!(cues.update(&sim) == [Sound::StatRecovered]);
+    }
+
+    #[test]
+    fn music_follows_the_life() {
+        let mut sim = sim(1);
+        sim.stats.belonging = 0.1;
+        assert!(music(&sim) == Some(synth::mood(&sim.stats, focus_stage(&sim))));
+        sim.game_state = GameState::Title;
+        assert!(music(&sim) == Some(synth::mood(&Stats::new(), 0)));
+        sim.game_state = GameState::GameOver;
+        assert!(music(&sim).is_none());
+    }
+
+    #[test]
+    fn the_null_backend_takes_anything() {
+        let mut sim = sim(2);
+        let mut audio = Audio::null(Volume::default());
+        let mut volume = audio.volume();
+        for i in 0..(300.0 / STEP) as u32 {
+            live(&mut sim, 1);
+            if i % 500 == 0 {
+                volume.music = step_down(volume.music);
+                audio.set_volume(volume);
+            }
+            audio.update(&sim, i % 700 < 30);
+        }
+        assert!(matches!(sim.game_state, GameState::GameOver));
+        audio.reset(&sim);
+        audio.update(&sim, false);
+    }
+}
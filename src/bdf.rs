@@ -0,0 +1,171 @@
+// Loading BDF (Glyph Bitmap Distribution Format) fonts. Glyph encodings are
+// taken to be Unicode, as they are in ISO10646 fonts; glyphs without one
+// (ENCODING -1) are skipped.
+use image::DynamicImage;
+
+use crate::font::{self, Font, Glyph};
+
+struct Partial {
+    code: Option<u32>,
+    advance: Option<i32>,
+    bbx: [i32; 4], // width, height, x offset, y offset from the origin
+    rows: Vec<Vec<bool>>,
+    in_bitmap: bool,
+}
+
+fn numbers(line: &str, n: usize, lineno: usize) -> Result<Vec<i32>, String> {
+    let nums: Vec<i32> = line.split_whitespace().skip(1).take(n)
+        .map(|s| s.parse::<i32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("bdf: line {}: expected {} numbers", lineno, n))?;
+    if nums.len() != n {
+        return Err(format!("bdf: line {}: expected {} numbers", lineno, n));
+    }
+    Ok(nums)
+}
+
+fn hex_row(line: &str, width: i32, lineno: usize) -> Result<Vec<bool>, String> {
+    let mut row = vec![];
+    for c in line.trim().chars() {
+        let nibble = c.to_digit(16)
+            .ok_or_else(|| format!("bdf: line {}: bad bitmap row", lineno))?;
+        for bit in (0..4).rev() {
+            row.push(nibble & (1 << bit) != 0);
+        }
+    }
+    row.truncate(width.max(0) as usize);
+    Ok(row)
+}
+
+pub fn load(src: &str) -> Result<(Font, DynamicImage), String> {
+    let mut font_bbx = None;
+    let mut ascent = None;
+    let mut descent = None;
+    let mut default_advance = None;
+    let mut partials = vec![];
+    let mut current: Option<Partial> = None;
+
+    for (i, line) in src.lines().enumerate() {
+        let lineno = i + 1;
+        let keyword = line.split_whitespace().next().unwrap_or("");
+        if let Some(p) = current.as_mut() {
+            if p.in_bitmap && keyword != "ENDCHAR" {
+                p.rows.push(hex_row(line, p.bbx[0], lineno)?);
+                continue;
+            }
+            match keyword {
+                "ENCODING" => {
+                    let code = numbers(line, 1, lineno)?[0];
+                    p.code = if code < 0 { None } else { Some(code as u32) };
+                }
+                "DWIDTH" => p.advance = Some(numbers(line, 2, lineno)?[0]),
+                "BBX" => {
+                    let n = numbers(line, 4, lineno)?;
+                    p.bbx = [n[0], n[1], n[2], n[3]];
+                }
+                "BITMAP" => p.in_bitmap = true,
+                "ENDCHAR" => partials.push(current.take().unwrap()),
+                _ => {}
+            }
+            continue;
+        }
+        match keyword {
+            "FONTBOUNDINGBOX" => font_bbx = Some(numbers(line, 4, lineno)?),
+            "FONT_ASCENT" => ascent = Some(numbers(line, 1, lineno)?[0]),
+            "FONT_DESCENT" => descent = Some(numbers(line, 1, lineno)?[0]),
+            "DWIDTH" => default_advance = Some(numbers(line, 2, lineno)?[0]),
+            "STARTCHAR" => {
+                current = Some(Partial {
+                    code: None,
+                    advance: None,
+                    bbx: [0, 0, 0, 0],
+                    rows: vec![],
+                    in_bitmap: false,
+                });
+            }
+            _ => {}
+        }
+    }
+    if current.is_some() {
+        return Err("bdf: last character has no ENDCHAR".to_string());
+    }
+
+    let fb = font_bbx.ok_or("bdf: no FONTBOUNDINGBOX")?;
+    // the cell is the font's bounding box, with the baseline where the
+    // bounding box puts it
+    let cell = [fb[0].max(0) as u32, fb[1].max(0) as u32];
+    let baseline = fb[1] + fb[3];
+    let height = match (ascent, descent) {
+        (Some(a), Some(d)) => a + d,
+        _ => fb[1],
+    };
+
+    let mut glyphs = vec![];
+    for p in partials.into_iter() {
+        let c = match p.code.and_then(std::char::from_u32) {
+            Some(c) => c,
+            None => continue,
+        };
+        glyphs.push(Glyph {
+            chars: vec![c],
+            advance: p.advance.or(default_advance).unwrap_or(fb[0]),
+            left: p.bbx[2] - fb[2],
+            top: baseline - (p.bbx[3] + p.bbx[1]),
+            rows: p.rows,
+        });
+    }
+    font::atlas(&glyphs, cell, height + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static FONT: &str = "\
+STARTFONT 2.1
+FONT test
+SIZE 4 75 75
+FONTBOUNDINGBOX 4 4 0 -1
+FONT_ASCENT 3
+FONT_DESCENT 1
+CHARS 2
+STARTCHAR A
+ENCODING 65
+DWIDTH 5 0
+BBX 3 3 0 0
+BITMAP
+40
+A0
+E0
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+BBX 1 1 0 0
+BITMAP
+80
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn loads_glyphs_above_the_baseline() {
+        let (font, img) = load(FONT).unwrap();
+        assert_eq!(font.cell, [4, 4]);
+        assert_eq!(font.line_height, 5);
+        assert_eq!(font.glyph('A'), [0, 0]);
+        assert_eq!(font.advance('A'), 5);
+        let img = img.to_rgba();
+        let on = |x, y| img.get_pixel(x, y)[3] != 0;
+        assert!(!on(0, 0) && on(1, 0) && !on(2, 0));
+        assert!(on(0, 1) && !on(1, 1) && on(2, 1));
+        assert!(on(0, 2) && on(1, 2) && on(2, 2));
+        // the descent is left empty
+        assert!(!on(0, 3) && !on(1, 3) && !on(2, 3));
+    }
+
+    #[test]
+    fn missing_endchar_is_an_error() {
+        let cut = &FONT[..FONT.find("ENDCHAR").unwrap()];
+        assert!(load(cut).is_err());
+    }
+}
@@ -0,0 +1,344 @@
+// Scripted life events, loaded from assets/events.toml. See that file for the
+// format.
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::simulation::*;
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Events {
+    // chance per week that one of the random events happens
+    #[serde(default)]
+    pub random_chance: f32,
+    #[serde(rename = "event")]
+    pub list: Vec<Event>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+pub enum AgeTrigger {
+    Exact(u32),
+    Range { min: u32, max: u32 },
+}
+
+impl AgeTrigger {
+    pub fn contains(&self, age: u32) -> bool {
+        match *self {
+            AgeTrigger::Exact(a) => age == a,
+            AgeTrigger::Range { min, max } => age >= min && age <= max,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Conditions {
+    pub married: Option<bool>,
+    pub min_moves: Option<u32>,
+    pub max_moves: Option<u32>,
+    pub home: Option<String>,
+    pub above: StatChange, // every listed stat must be > the value
+    pub below: StatChange, // every listed stat must be < the value
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FocusFactors {
+    pub play: Option<f32>,
+    pub socialize: Option<f32>,
+    pub research: Option<f32>,
+    pub create: Option<f32>,
+    pub work: Option<f32>,
+}
+
+impl FocusFactors {
+    pub fn get(&self, f: Focus) -> f32 {
+        match f {
+            Focus::Play => self.play,
+            Focus::Socialize => self.socialize,
+            Focus::Research => self.research,
+            Focus::Create => self.create,
+            Focus::Work => self.work,
+        }.unwrap_or(1.0)
+    }
+}
+
+// How likely a random event is to be picked, relative to the others:
+// (base + sum of stat * factor) * the factor for the current focus.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Weight {
+    pub base: f32,
+    #[serde(default)]
+    pub stats: StatChange,
+    #[serde(default)]
+    pub focus: FocusFactors,
+}
+
+impl Weight {
+    pub fn get(&self, sim: &Simulation) -> f32 {
+        let mut w = self.base;
+        for (stat, factor) in self.stats.iter() {
+            w += sim.stats.get(*stat) * factor;
+        }
+        w.max(0.0) * self.focus.get(sim.current_focus)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Choice {
+    pub id: String, // shown as the string choice.<event id>.<id>
+    // move events only: where to move; none stays put
+    #[serde(default)]
+    pub city: Option<String>,
+    #[serde(default)]
+    pub effects: StatChange,
+    #[serde(default)]
+    pub married: Option<bool>,
+    // kids events only: picking this has a child
+    #[serde(default)]
+    pub kid: bool,
+    // only offered when this holds
+    #[serde(default)]
+    pub require: Conditions,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Event {
+    pub id: String,
+    pub kind: ModalKind,
+    // Either the player's age, or a child's: kid_age events are offered for
+    // each child as they turn that age (see kids.rs).
+    #[serde(default)]
+    pub age: Option<AgeTrigger>,
+    #[serde(default)]
+    pub kid_age: Option<u32>,
+    #[serde(default)]
+    pub require: Conditions,
+    pub choices: Vec<Choice>,
+    // Random events have a weight and only happen when rolled for.
+    #[serde(default)]
+    pub weight: Option<Weight>,
+    #[serde(default)]
+    pub repeat: bool,
+}
+
+impl Event {
+    // Whether the player's age is one this event can happen at.
+    pub fn at_age(&self, age: u32) -> bool {
+        self.age.as_ref().map_or(false, |a| a.contains(age))
+    }
+}
+
+// Stands in for the name of the current home city in a move choice's city.
+pub static HOME: &str = "$home";
+
+pub fn load(src: &str, cities: &[City]) -> Result<Events, String> {
+    let events: Events = toml::from_str(src)
+        .map_err(|e| format!("events: {}", e))?;
+
+    if events.random_chance < 0.0 || events.random_chance > 1.0 {
+        return Err("events: random_chance must be between 0 and 1".to_string());
+    }
+
+    let mut ids = HashSet::new();
+    for ev in events.list.iter() {
+        if !ids.insert(ev.id.as_str()) {
+            return Err(format!("events: duplicate event id '{}'", ev.id));
+        }
+        if ev.choices.is_empty() {
+            return Err(format!("events: '{}' has no choices", ev.id));
+        }
+        let mut choice_ids = HashSet::new();
+        for c in ev.choices.iter() {
+            if !choice_ids.insert(c.id.as_str()) {
+                return Err(format!("events: '{}' has two choices '{}'", ev.id, c.id));
+            }
+            if let Some(home) = &c.require.home {
+                if !cities.iter().any(|c| str_eq(&c.name, home)) {
+                    return Err(format!("events: '{}' choice '{}' requires unknown home '{}'",
+                                       ev.id, c.id, home));
+                }
+            }
+        }
+        // there has to be something to pick whatever the player's situation
+        if ev.choices.iter().all(|c| has_conditions(&c.require)) {
+            return Err(format!("events: '{}' has no choice without `require`", ev.id));
+        }
+        if ev.age.is_some() == ev.kid_age.is_some() {
+            return Err(format!("events: '{}' needs one of age or kid_age", ev.id));
+        }
+        if ev.kid_age.is_some() && ev.weight.is_some() {
+            return Err(format!("events: '{}' has a kid_age so can't be random", ev.id));
+        }
+        if let Some(AgeTrigger::Range { min, max }) = ev.age {
+            if min > max {
+                return Err(format!("events: '{}' has age min {} > max {}", ev.id, min, max));
+            }
+        }
+        if ev.repeat && ev.weight.is_none() {
+            return Err(format!("events: '{}' repeats but has no weight", ev.id));
+        }
+        if let Some(home) = &ev.require.home {
+            if !cities.iter().any(|c| str_eq(&c.name, home)) {
+                return Err(format!("events: '{}' requires unknown home '{}'", ev.id, home));
+            }
+        }
+        let is_move = if let ModalKind::Move = ev.kind { true } else { false };
+        let is_kids = if let ModalKind::Kids = ev.kind { true } else { false };
+        for c in ev.choices.iter() {
+            if c.kid && !is_kids {
+                return Err(format!("events: '{}' isn't a kids event but '{}' has a kid",
+                                   ev.id, c.id));
+            }
+            let city = match &c.city {
+                Some(city) => city,
+                None => continue,
+            };
+            if !is_move {
+                return Err(format!("events: '{}' isn't a move but '{}' has a city",
+                                   ev.id, c.id));
+            }
+            let known = city == HOME || cities.iter().any(|c| str_eq(&c.name, city));
+            if !known {
+                return Err(format!("events: '{}' moves to unknown city '{}'", ev.id, city));
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+fn has_conditions(c: &Conditions) -> bool {
+    c.married.is_some() || c.min_moves.is_some() || c.max_moves.is_some() ||
+        c.home.is_some() || !c.above.is_empty() || !c.below.is_empty()
+}
+
+pub fn conditions_met(sim: &Simulation, c: &Conditions) -> bool {
+    if let Some(married) = c.married {
+        if sim.married != married { return false; }
+    }
+    if let Some(min) = c.min_moves {
+        if sim.moves < min { return false; }
+    }
+    if let Some(max) = c.max_moves {
+        if sim.moves > max { return false; }
+    }
+    if let Some(home) = &c.home {
+        if !str_eq(&home_city(sim).name, home) { return false; }
+    }
+    for (stat, v) in c.above.iter() {
+        if !(sim.stats.get(*stat) > *v) { return false; }
+    }
+    for (stat, v) in c.below.iter() {
+        if !(sim.stats.get(*stat) < *v) { return false; }
+    }
+    true
+}
+
+// The choices as they should be offered right now, with placeholders filled
+// in and those whose `require` doesn't hold left out.
+pub fn resolve_choices(sim: &Simulation, ev: &Event) -> Vec<Choice> {
+    ev.choices.iter().filter(|c| conditions_met(sim, &c.require)).map(|c| {
+        let mut c = c.clone();
+        if c.city.as_ref().map_or(false, |city| city == HOME) {
+            c.city = Some(home_city(sim).name);
+        }
+        c
+    }).collect()
+}
+
+// Once a week, maybe start one of the random events that could happen now.
+pub fn roll_random(sim: &mut Simulation) {
+    if sim.rng.next_f32() >= sim.events.random_chance {
+        return;
+    }
+
+    let mut weights = vec![];
+    for (i, ev) in sim.events.list.iter().enumerate() {
+        if let Some(weight) = &ev.weight {
+            if ev.at_age(sim.age) {
+                weights.push((i, weight.get(sim)));
+            }
+        }
+    }
+    // events that are done or whose conditions fail can't be picked
+    weights.retain(|&(i, _)| {
+        let ev = &sim.events.list[i];
+        (ev.repeat || !sim.modals_done.iter().any(|m| m.event == ev.id)) &&
+            conditions_met(sim, &ev.require)
+    });
+
+    let total: f32 = weights.iter().map(|&(_, w)| w).sum();
+    if total <= 0.0 {
+        return;
+    }
+    let mut r = sim.rng.next_f32() * total;
+    let last = weights.len() - 1;
+    for (n, (i, w)) in weights.into_iter().enumerate() {
+        if r < w || n == last {
+            let ev = sim.events.list[i].clone();
+            maybe_start_modal(sim, &ev);
+            return;
+        }
+        r -= w;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::region;
+    use crate::simulation::tests::{at_age, sim};
+    use crate::simulation::*;
+
+    #[test]
+    fn moves_to_unknown_cities_are_refused() {
+        let cities = region::load(r#"
+            map = "map.png"
+            [[city]]
+            name = "Toronto"
+            position = [0, 0]
+            home = true
+            [[city]]
+            name = "NY"
+            position = [1, 0]
+        "#).unwrap().city;
+        let go = |city: &str| format!(r#"
+            [[event]]
+            id = "go"
+            kind = "move"
+            age = 20
+            choices = [{{ id = "stay" }}, {{ id = "go", city = "{}" }}]
+        "#, city);
+        assert!(super::load(&go("ny"), &cities).is_ok());
+        assert!(super::load(&go("$home"), &cities).is_ok());
+        let e = super::load(&go("la"), &cities).err().unwrap();
+        assert!(e.contains("unknown city 'la'"), "{}", e);
+        // the built in events move to cities this region doesn't have
+        assert!(super::load(include_str!("../assets/events.toml"), &cities).is_err());
+    }
+
+    #[test]
+    fn open_modal_doesnt_skip_an_age() {
+        let mut sim = sim(1);
+        at_age(&mut sim, 29.95);
+        let illness = sim.events.list.iter().find(|ev| ev.id == "illness").unwrap().clone();
+        start_modal(&mut sim, &illness);
+        // left open for longer than a year
+        for _ in 0..(5.0 / STEP) as u32 {
+            sim.step(STEP);
+        }
+        assert_eq!(sim.age, 29);
+        sim.command(Command::Confirm);
+        for _ in 0..(1.0 / STEP) as u32 {
+            if let GameState::Modal = sim.game_state {
+                break;
+            }
+            sim.step(STEP);
+        }
+        assert_eq!(sim.current_modal.as_ref().unwrap().event, "marriage");
+    }
+}
@@ -0,0 +1,193 @@
+// A life written out for analysis or sharing: the whole record as JSON
+// (LifeExport below is the schema) and the weekly history as CSV. Written
+// when a life ends and whenever the player asks. Analysis scripts rely on
+// these, so bump EXPORT_VERSION whenever a field is renamed, removed or
+// changes meaning; adding a field doesn't need it.
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::simulation::*;
+use crate::summary;
+
+pub static EXPORT_VERSION: u32 = 1;
+pub static JSON_PATH: &str = "life.json";
+pub static CSV_PATH: &str = "life.csv";
+
+#[derive(Serialize)]
+pub struct LifeExport {
+    pub version: u32, // EXPORT_VERSION
+    pub seed: u32,
+    pub age: u32,
+    pub over: bool, // false for a life exported while still being lived
+    pub epitaph: Option<String>, // over lives only; see summary.rs
+    pub married: bool,
+    pub moves: u32,
+    pub stats: BTreeMap<Stat, f32>, // as they are now (or ended)
+    pub birthplace: String,
+    pub home: String,
+    pub cities: Vec<CityExport>, // every city, lived in or not
+    pub kids: Vec<KidExport>,
+    pub decisions: Vec<DecisionExport>, // oldest first
+    pub journeys: Vec<JourneyExport>, // moves and visits made from the map, oldest first
+    pub stride: u64, // weeks between entries of `weeks`
+    pub weeks: Vec<WeekExport>, // oldest first
+}
+
+#[derive(Serialize)]
+pub struct CityExport {
+    pub name: String,
+    pub weeks: f32, // lived there
+    pub friends: Vec<f32>, // the bond with each friend there, 0 to 1
+}
+
+#[derive(Serialize)]
+pub struct KidExport {
+    pub age: u32,
+    pub bond: f32,
+    pub home: bool, // not yet moved out
+}
+
+#[derive(Serialize)]
+pub struct DecisionExport {
+    pub age: u32,
+    pub event: String, // id in events.toml
+    pub choice: String, // choice id within the event
+    pub city: Option<String>, // where a move choice went
+}
+
+#[derive(Serialize)]
+pub struct JourneyExport {
+    pub age: u32,
+    pub action: Action, // move or travel
+    pub city: String,
+}
+
+#[derive(Serialize)]
+pub struct WeekExport {
+    pub week: u64, // since birth; 50 weeks a year
+    pub focus: &'static str, // play, socialize, research, create or work
+    pub home: String,
+    pub stats: BTreeMap<Stat, f32>,
+}
+
+pub fn focus_name(f: Focus) -> &'static str {
+    match f {
+        Focus::Play => "play",
+        Focus::Socialize => "socialize",
+        Focus::Research => "research",
+        Focus::Create => "create",
+        Focus::Work => "work",
+    }
+}
+
+pub fn life(sim: &Simulation) -> LifeExport {
+    let over = match sim.game_state {
+        GameState::GameOver => true,
+        _ => false,
+    };
+    LifeExport {
+        version: EXPORT_VERSION,
+        seed: sim.seed,
+        age: sim.age,
+        over: over,
+        epitaph: if over { Some(summary::summarize(sim).epitaph.to_string()) } else { None },
+        married: sim.married,
+        moves: sim.moves,
+        stats: STATS.iter().map(|&s| (s, sim.stats.get(s))).collect(),
+        birthplace: sim.cities[sim.birthplace].name.clone(),
+        home: sim.cities[sim.current_city].name.clone(),
+        cities: sim.cities.iter().map(|c| CityExport {
+            name: c.name.clone(),
+            weeks: c.exp,
+            friends: c.friends.iter().map(|f| f.bond).collect(),
+        }).collect(),
+        kids: sim.kids.iter().map(|k| KidExport {
+            age: k.age(),
+            bond: k.bond,
+            home: k.home,
+        }).collect(),
+        decisions: sim.modals_done.iter().map(|m| {
+            let choice = &m.choices[m.selection as usize];
+            DecisionExport {
+                age: m.age,
+                event: m.event.clone(),
+                choice: choice.id.clone(),
+                city: choice.city.clone(),
+            }
+        }).collect(),
+        journeys: sim.journeys.iter().map(|j| JourneyExport {
+            age: j.age,
+            action: j.action,
+            city: sim.cities[j.city].name.clone(),
+        }).collect(),
+        stride: sim.history.stride,
+        weeks: sim.history.samples.iter().map(|s| WeekExport {
+            week: s.week,
+            focus: focus_name(s.focus),
+            home: sim.cities[s.home].name.clone(),
+            stats: STATS.iter().map(|&stat| (stat, s.get(stat))).collect(),
+        }).collect(),
+    }
+}
+
+pub fn to_json(sim: &Simulation) -> Result<String, String> {
+    serde_json::to_string_pretty(&life(sim)).map_err(|e| format!("export: {}", e))
+}
+
+// One row a week sampled: week, age, focus, home, then every stat in the
+// order of STATS, with a header row naming the columns.
+pub fn to_csv(sim: &Simulation) -> String {
+    let mut header = vec!["week".to_string(), "age".to_string(),
+                          "focus".to_string(), "home".to_string()];
+    header.extend(STATS.iter().map(|&s| String::from(s)));
+    let mut csv = header.join(",");
+    csv.push('\n');
+    for s in sim.history.samples.iter() {
+        let mut row = vec![s.week.to_string(), (s.week / 50).to_string(),
+                           focus_name(s.focus).to_string(), csv_field(&sim.cities[s.home].name)];
+        row.extend(s.stats.iter().map(|v| v.to_string()));
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+// City names come from region files, so may need quoting.
+fn csv_field(s: &str) -> String {
+    if s.contains(|c| c == ',' || c == '"' || c == '\n') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+// Write JSON_PATH and CSV_PATH into dir.
+pub fn write(sim: &Simulation, dir: &Path) -> Result<(), String> {
+    let json = dir.join(JSON_PATH);
+    std::fs::write(&json, to_json(sim)?)
+        .map_err(|e| format!("export: {}: {}", json.display(), e))?;
+    let csv = dir.join(CSV_PATH);
+    std::fs::write(&csv, to_csv(sim))
+        .map_err(|e| format!("export: {}: {}", csv.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::tests::{at_age, city, sim};
+    use crate::simulation::*;
+
+    #[test]
+    fn moves_from_the_map_are_exported() {
+        let mut sim = sim(1);
+        at_age(&mut sim, 20.0);
+        sim.stats.money = 1.0;
+        sim.command(Command::Plan(Action::Move));
+        sim.command(Command::Select(city(&sim, "NY") as i32));
+        sim.command(Command::Confirm);
+        let life = super::life(&sim);
+        assert_eq!(life.journeys.len(), 1);
+        assert_eq!((life.journeys[0].age, life.journeys[0].city.as_str()), (20, "NY"));
+        assert!(super::to_json(&sim).unwrap().contains("\"action\": \"move\""));
+    }
+}
@@ -0,0 +1,274 @@
+// Laying out text in a bitmap font. The built in glyphs are in
+// assets/font.png and their metrics in assets/font.toml; see the latter for
+// the format. A BDF or PSF font can be loaded instead (see bdf.rs, psf.rs),
+// which is drawn into an atlas laid out the same way.
+use image::{DynamicImage, Rgba, RgbaImage};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+#[derive(Clone, Copy)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FontFile {
+    cell: [u32; 2],
+    columns: u32,
+    first: u32,
+    #[serde(default)]
+    extra: String,
+    line_height: i32,
+    advance: i32,
+    #[serde(default)]
+    advances: BTreeMap<String, i32>,
+}
+
+pub struct Font {
+    pub cell: [u32; 2],
+    pub columns: u32,
+    pub line_height: i32,
+    cells: HashMap<char, u32>, // which cell each character is drawn from
+    advances: Vec<i32>, // by cell
+    missing: u32, // the cell drawn for characters the font doesn't have
+}
+
+static MISSING: char = '?';
+
+// A glyph from a font file, ready to go in an atlas.
+pub struct Glyph {
+    pub chars: Vec<char>, // every character drawn with this glyph
+    pub advance: i32,
+    pub left: i32, // where the bitmap goes, relative to the cell's top left
+    pub top: i32,
+    pub rows: Vec<Vec<bool>>,
+}
+
+pub fn load(src: &str) -> Result<Font, String> {
+    let file: FontFile = toml::from_str(src).map_err(|e| format!("font: {}", e))?;
+    if file.cell[0] == 0 || file.cell[1] == 0 || file.columns == 0 {
+        return Err("font: cell and columns must not be 0".to_string());
+    }
+
+    // printable ASCII, space to ~
+    if file.first > ' ' as u32 {
+        return Err(format!("font: first must be at most {}", ' ' as u32));
+    }
+    let mut cells = HashMap::new();
+    for code in file.first..='~' as u32 {
+        if let Some(c) = std::char::from_u32(code) {
+            cells.insert(c, code - file.first);
+        }
+    }
+    for c in file.extra.chars() {
+        let next = cells.len() as u32;
+        if cells.insert(c, next).is_some() {
+            return Err(format!("font: '{}' is in extra but already in the font", c));
+        }
+    }
+    let mut advances = vec![file.advance; cells.len()];
+    for (s, &advance) in file.advances.iter() {
+        let mut chars = s.chars();
+        let c = match (chars.next(), chars.next()) {
+            (Some(c), None) => c,
+            _ => return Err(format!("font: '{}' in advances is not one character", s)),
+        };
+        match cells.get(&c) {
+            Some(&i) => advances[i as usize] = advance,
+            None => return Err(format!("font: '{}' in advances is not in the font", s)),
+        }
+    }
+
+    Ok(Font {
+        cell: file.cell,
+        columns: file.columns,
+        line_height: file.line_height,
+        missing: cells.get(&MISSING).cloned().unwrap_or(0),
+        cells: cells,
+        advances: advances,
+    })
+}
+
+// Load a BDF or PSF font, by its extension, as a Font and its atlas.
+pub fn load_file(path: &Path) -> Result<(Font, DynamicImage), String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("font: {}: {}", path.display(), e))?;
+    let loaded = match path.extension().and_then(|e| e.to_str()) {
+        Some("bdf") => {
+            let src = String::from_utf8(bytes)
+                .map_err(|_| "font: a BDF font must be text".to_string())?;
+            crate::bdf::load(&src)
+        }
+        Some("psf") | Some("psfu") => crate::psf::load(&bytes),
+        _ => Err("font: only .bdf and .psf fonts can be loaded".to_string()),
+    };
+    loaded.map_err(|e| format!("{} ({})", e, path.display()))
+}
+
+static ATLAS_COLUMNS: u32 = 16;
+
+// Draw glyphs into a grid of cells and describe it as a Font.
+pub fn atlas(glyphs: &[Glyph], cell: [u32; 2], line_height: i32)
+             -> Result<(Font, DynamicImage), String> {
+    if glyphs.is_empty() {
+        return Err("font: no glyphs".to_string());
+    }
+    if cell[0] == 0 || cell[1] == 0 {
+        return Err("font: glyphs have no size".to_string());
+    }
+    let rows = (glyphs.len() as u32 + ATLAS_COLUMNS - 1) / ATLAS_COLUMNS;
+    // even sizes keep the texture on whole pixels when it's drawn centered
+    let w = (ATLAS_COLUMNS * cell[0] + 1) / 2 * 2;
+    let h = (rows * cell[1] + 1) / 2 * 2;
+    let mut img = RgbaImage::from_pixel(w, h, Rgba([0, 0, 0, 0]));
+
+    let mut cells = HashMap::new();
+    let mut advances = vec![];
+    for (i, g) in glyphs.iter().enumerate() {
+        let i = i as u32;
+        let x0 = (i % ATLAS_COLUMNS * cell[0]) as i32;
+        let y0 = (i / ATLAS_COLUMNS * cell[1]) as i32;
+        for (y, row) in g.rows.iter().enumerate() {
+            for (x, &on) in row.iter().enumerate() {
+                let (x, y) = (g.left + x as i32, g.top + y as i32);
+                // pixels outside the cell would bleed into the neighbours
+                if on && x >= 0 && y >= 0 && x < cell[0] as i32 && y < cell[1] as i32 {
+                    img.put_pixel((x0 + x) as u32, (y0 + y) as u32, Rgba([255, 255, 255, 255]));
+                }
+            }
+        }
+        for &c in g.chars.iter() {
+            cells.entry(c).or_insert(i);
+        }
+        advances.push(g.advance);
+    }
+
+    let font = Font {
+        cell: cell,
+        columns: ATLAS_COLUMNS,
+        line_height: line_height,
+        missing: cells.get(&MISSING).cloned().unwrap_or(0),
+        cells: cells,
+        advances: advances,
+    };
+    Ok((font, DynamicImage::ImageRgba8(img)))
+}
+
+impl Font {
+    fn index(&self, c: char) -> u32 {
+        self.cells.get(&c).cloned().unwrap_or(self.missing)
+    }
+
+    // Which cell of the font's texture holds c, as [column, row].
+    pub fn glyph(&self, c: char) -> [u32; 2] {
+        let i = self.index(c);
+        [i % self.columns, i / self.columns]
+    }
+
+    pub fn advance(&self, c: char) -> i32 {
+        self.advances[self.index(c) as usize]
+    }
+
+    pub fn width(&self, line: &str) -> i32 {
+        line.chars().map(|c| self.advance(c)).sum()
+    }
+
+    // Break text into lines no wider than max_width, between words where
+    // possible. Newlines in the text always start a new line.
+    pub fn wrap(&self, text: &str, max_width: i32) -> Vec<String> {
+        let mut lines = vec![];
+        for paragraph in text.lines() {
+            let mut line = String::new();
+            for word in paragraph.split(' ') {
+                let candidate = if line.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{} {}", line, word)
+                };
+                if self.width(&candidate) <= max_width {
+                    line = candidate;
+                    continue;
+                }
+                if !line.is_empty() {
+                    lines.push(line);
+                }
+                // a word too long for a line of its own is split wherever
+                line = String::new();
+                for c in word.chars() {
+                    if !line.is_empty() && self.width(&line) + self.advance(c) > max_width {
+                        lines.push(line);
+                        line = String::new();
+                    }
+                    line.push(c);
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+
+    // Where each character of lines goes: the left edge of its cell and the
+    // middle of its line. p is the top line's anchor; which side of the text
+    // it is on depends on align.
+    pub fn layout(&self, lines: &[String], p: [i32; 2], align: Align) -> Vec<([i32; 2], char)> {
+        let mut glyphs = vec![];
+        for (n, line) in lines.iter().enumerate() {
+            let mut x = match align {
+                Align::Left => p[0],
+                Align::Center => p[0] - self.width(line) / 2,
+                Align::Right => p[0] - self.width(line),
+            };
+            let y = p[1] + self.line_height * n as i32;
+            for c in line.chars() {
+                glyphs.push(([x, y], c));
+                x += self.advance(c);
+            }
+        }
+        glyphs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every character 4 wide, except i at 2.
+    fn font() -> Font {
+        load("cell = [8, 8]\ncolumns = 16\nfirst = 32\nline_height = 10\n\
+              advance = 4\nadvances = { i = 2 }\n").unwrap()
+    }
+
+    #[test]
+    fn wraps_between_words() {
+        let font = font();
+        assert_eq!(font.wrap("the cat sat", 28), ["the cat", "sat"]);
+        assert_eq!(font.wrap("the cat sat", 27), ["the", "cat", "sat"]);
+        assert_eq!(font.wrap("iiii iiii", 20), ["iiii iiii"]);
+        assert_eq!(font.wrap("iiii iiii", 19), ["iiii", "iiii"]);
+        assert_eq!(font.wrap("a\nb c", 100), ["a", "b c"]);
+    }
+
+    #[test]
+    fn splits_a_word_too_long_for_a_line() {
+        let font = font();
+        assert_eq!(font.wrap("abcdefghij", 16), ["abcd", "efgh", "ij"]);
+        assert_eq!(font.wrap("go abcdefghij", 16), ["go", "abcd", "efgh", "ij"]);
+        // even a line too narrow for one character gets one
+        assert_eq!(font.wrap("ab", 1), ["a", "b"]);
+    }
+
+    #[test]
+    fn aligns_each_line_on_its_own() {
+        let font = font();
+        let lines = ["ab".to_string(), "i".to_string()];
+        let xs = |align| -> Vec<[i32; 2]> {
+            font.layout(&lines, [100, 10], align).iter().map(|&(p, _)| p).collect()
+        };
+        assert_eq!(xs(Align::Left), [[100, 10], [104, 10], [100, 20]]);
+        assert_eq!(xs(Align::Center), [[96, 10], [100, 10], [99, 20]]);
+        assert_eq!(xs(Align::Right), [[92, 10], [96, 10], [98, 20]]);
+    }
+}
@@ -0,0 +1,167 @@
+// Friends, kept in the city they live in. Socializing makes friends in the
+// home city and brings them closer; friends left alone drift away, faster
+// the farther off they are, and are lost when the bond is gone. Belonging
+// comes from the friends you have. The rates are in assets/rules.toml.
+use crate::kids;
+use crate::simulation::*;
+
+static DISTANCE_UNIT: f32 = 100.0; // map pixels
+
+fn distance(a: &City, b: &City) -> f32 {
+    let dx = (a.position[0] - b.position[0]) as f32;
+    let dy = (a.position[1] - b.position[1]) as f32;
+    (dx * dx + dy * dy).sqrt()
+}
+
+// Apply one frame's worth (dweek weeks) of friendship.
+pub fn apply(sim: &mut Simulation, dweek: f32) {
+    let rules = sim.rules.friends.clone();
+    let home = home_city(sim);
+    let socializing = sim.current_focus == Focus::Socialize;
+    // kids at home take some of the time spent socializing
+    let free = kids::free_time(sim);
+
+    if socializing {
+        let room = home.friends.len() < rules.max_per_city;
+        if room && sim.rng.next_f32() < rules.meet * free * dweek {
+            let compatibility = sim.rng.next_f32();
+            let city = sim.cities.iter_mut().find(|c| c.home).unwrap();
+            city.friends.push(Friend {
+                bond: rules.start_bond,
+                compatibility: compatibility,
+                exp: 0.0,
+            });
+        }
+    }
+
+    let mut belonging = 0.0;
+    for city in sim.cities.iter_mut() {
+        let away = distance(city, &home) / DISTANCE_UNIT;
+        for f in city.friends.iter_mut() {
+            if city.home && socializing {
+                f.bond = (f.bond + rules.grow * f.compatibility * free * dweek).min(1.0);
+                f.exp += dweek;
+            } else if city.home {
+                f.bond -= rules.neglect * dweek;
+            } else {
+                f.bond -= rules.distance * away * dweek;
+            }
+        }
+        city.friends.retain(|f| f.bond > 0.0);
+        let bonds: f32 = city.friends.iter().map(|f| f.bond).sum();
+        belonging += rules.belonging * bonds / (1.0 + away) * dweek;
+    }
+    sim.stats.belonging += belonging;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::tests::{city, sim};
+
+    fn friend(bond: f32) -> Friend {
+        Friend {
+            bond: bond,
+            compatibility: 1.0,
+            exp: 0.0,
+        }
+    }
+
+    // A life with no friends yet, doing focus.
+    fn alone(focus: Focus) -> Simulation {
+        let mut sim = sim(1);
+        for c in sim.cities.iter_mut() {
+            c.friends.clear();
+        }
+        sim.current_focus = focus;
+        sim
+    }
+
+    #[test]
+    fn socializing_makes_friends_at_home_up_to_the_limit() {
+        let mut sim = alone(Focus::Socialize);
+        sim.rules.friends.meet = 1.0;
+        for _ in 0..20 {
+            apply(&mut sim, 1.0);
+        }
+        assert_eq!(home_city(&sim).friends.len(), sim.rules.friends.max_per_city);
+        assert!(sim.cities.iter().filter(|c| !c.home).all(|c| c.friends.is_empty()));
+
+        let mut sim = alone(Focus::Work);
+        sim.rules.friends.meet = 1.0;
+        apply(&mut sim, 1.0);
+        assert!(home_city(&sim).friends.is_empty());
+    }
+
+    #[test]
+    fn bonds_grow_with_time_together_and_fade_without() {
+        let mut sim = alone(Focus::Socialize);
+        sim.rules.friends.meet = 0.0;
+        let home = sim.current_city;
+        sim.cities[home].friends.push(friend(0.5));
+        apply(&mut sim, 1.0);
+        let grown = 0.5 + sim.rules.friends.grow;
+        assert!((sim.cities[home].friends[0].bond - grown).abs() < 1e-6);
+        assert_eq!(sim.cities[home].friends[0].exp, 1.0);
+
+        sim.current_focus = Focus::Work;
+        apply(&mut sim, 1.0);
+        let faded = grown - sim.rules.friends.neglect;
+        assert!((sim.cities[home].friends[0].bond - faded).abs() < 1e-6);
+    }
+
+    #[test]
+    fn friends_farther_away_drift_off_faster() {
+        let mut sim = alone(Focus::Socialize);
+        sim.rules.friends.meet = 0.0;
+        let (near, far) = (city(&sim, "Ottawa"), city(&sim, "SF"));
+        sim.cities[near].friends.push(friend(0.5));
+        sim.cities[far].friends.push(friend(0.5));
+        apply(&mut sim, 1.0);
+
+        let home = home_city(&sim);
+        for &i in [near, far].iter() {
+            let away = distance(&sim.cities[i], &home) / DISTANCE_UNIT;
+            let expected = 0.5 - sim.rules.friends.distance * away;
+            assert!((sim.cities[i].friends[0].bond - expected).abs() < 1e-6);
+        }
+        assert!(sim.cities[far].friends[0].bond < sim.cities[near].friends[0].bond);
+    }
+
+    #[test]
+    fn a_friend_is_gone_when_the_bond_is() {
+        let mut sim = alone(Focus::Work);
+        let home = sim.current_city;
+        let neglect = sim.rules.friends.neglect;
+        sim.cities[home].friends.push(friend(neglect / 2.0));
+        sim.cities[home].friends.push(friend(0.5));
+        apply(&mut sim, 1.0);
+        assert_eq!(sim.cities[home].friends.len(), 1);
+        assert!((sim.cities[home].friends[0].bond - (0.5 - neglect)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn belonging_comes_from_friends_and_falls_off_with_distance() {
+        let mut sim = alone(Focus::Work);
+        sim.rules.friends.neglect = 0.0;
+        sim.rules.friends.distance = 0.0;
+        let before = sim.stats.belonging;
+        apply(&mut sim, 1.0);
+        assert_eq!(sim.stats.belonging, before);
+
+        let home = sim.current_city;
+        sim.cities[home].friends.push(friend(0.5));
+        apply(&mut sim, 1.0);
+        let from_home = sim.stats.belonging - before;
+        assert!((from_home - sim.rules.friends.belonging * 0.5).abs() < 1e-6);
+
+        sim.cities[home].friends.clear();
+        let far = city(&sim, "SF");
+        sim.cities[far].friends.push(friend(0.5));
+        let before = sim.stats.belonging;
+        apply(&mut sim, 1.0);
+        let away = distance(&sim.cities[far], &sim.cities[home]) / DISTANCE_UNIT;
+        let from_far = sim.stats.belonging - before;
+        assert!((from_far - from_home / (1.0 + away)).abs() < 1e-6);
+    }
+}
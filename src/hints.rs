@@ -0,0 +1,160 @@
+// First-time hints: a line at the top of the screen the first time
+// something new opens up, like a focus or planning moves. Which hints have
+// been shown is kept in the player's hints.toml, so each shows once ever
+// rather than once a life.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::input::Input;
+use crate::simulation::*;
+
+pub static HINTS_PATH: &str = "hints.toml";
+static HINT_SECS: u64 = 6;
+
+pub struct Hint {
+    pub id: &'static str, // shown as the string hint.<id>
+    pub inputs: &'static [Input], // whose bindings fill in the string's {}s
+    due: fn(&Simulation) -> bool,
+}
+
+fn always(_: &Simulation) -> bool { true }
+fn socialize(sim: &Simulation) -> bool { focus_is_unlocked(sim, Focus::Socialize) }
+fn research(sim: &Simulation) -> bool { focus_is_unlocked(sim, Focus::Research) }
+fn create(sim: &Simulation) -> bool { focus_is_unlocked(sim, Focus::Create) }
+fn work(sim: &Simulation) -> bool { focus_is_unlocked(sim, Focus::Work) }
+fn plan(sim: &Simulation) -> bool { sim.age >= sim.rules.trips.min_age }
+fn kids(sim: &Simulation) -> bool { !sim.kids.is_empty() }
+
+// In the order they're checked; only one shows at a time.
+static HINTS: [Hint; 7] = [
+    Hint { id: "focus", inputs: &[Input::Down], due: always },
+    Hint { id: "socialize", inputs: &[Input::Focus(Focus::Socialize)], due: socialize },
+    Hint { id: "research", inputs: &[Input::Focus(Focus::Research)], due: research },
+    Hint { id: "create", inputs: &[Input::Focus(Focus::Create)], due: create },
+    Hint { id: "work", inputs: &[Input::Focus(Focus::Work)], due: work },
+    Hint { id: "plan", inputs: &[Input::Plan(Action::Move), Input::Plan(Action::Travel)],
+           due: plan },
+    Hint { id: "kids", inputs: &[Input::Focus(Focus::Socialize)], due: kids },
+];
+
+// hints.toml: the ids of the hints already shown
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Seen {
+    pub hints: BTreeSet<String>,
+}
+
+// The player's hints.toml, or nothing seen if there isn't one.
+pub fn load_seen(path: &Path) -> Result<Seen, String> {
+    if !path.exists() {
+        return Ok(Seen::default());
+    }
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("hints: {}: {}", path.display(), e))?;
+    toml::from_str(&src).map_err(|e| format!("hints: {}: {}", path.display(), e))
+}
+
+pub fn write_seen(seen: &Seen, path: &Path) -> Result<(), String> {
+    let src = toml::to_string(seen).map_err(|e| format!("hints: {}", e))?;
+    std::fs::write(path, src).map_err(|e| format!("hints: {}: {}", path.display(), e))
+}
+
+pub struct Hints {
+    pub seen: Seen,
+    shown: Option<(&'static Hint, Instant)>,
+}
+
+impl Hints {
+    pub fn new(seen: Seen) -> Self {
+        Self { seen: seen, shown: None }
+    }
+
+    // The hint to draw now, if any.
+    pub fn shown(&self) -> Option<&'static Hint> {
+        self.shown.map(|(h, _)| h)
+    }
+
+    // Call once a frame. Once the last hint has been up long enough, shows
+    // the first one that's due and not yet seen; true if one was, and Seen
+    // should be written.
+    pub fn update(&mut self, sim: &Simulation) -> bool {
+        if let Some((_, since)) = self.shown {
+            if since.elapsed() < Duration::from_secs(HINT_SECS) {
+                return false;
+            }
+            self.shown = None;
+        }
+        match sim.game_state {
+            GameState::Game => {}
+            _ => return false,
+        }
+        for h in HINTS.iter() {
+            if !self.seen.hints.contains(h.id) && (h.due)(sim) {
+                self.seen.hints.insert(h.id.to_string());
+                self.shown = Some((h, Instant::now()));
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kids;
+    use crate::simulation::tests::{at_age, sim};
+
+    // A life far enough along for every hint to be due.
+    fn grown_up() -> Simulation {
+        let mut sim = sim(1);
+        at_age(&mut sim, 30.0);
+        kids::born(&mut sim);
+        sim.game_state = GameState::Game;
+        sim
+    }
+
+    // The hints shown one after another, without waiting each one out.
+    fn show_all(hints: &mut Hints, sim: &Simulation) -> Vec<&'static str> {
+        let mut ids = Vec::new();
+        while hints.update(sim) {
+            ids.push(hints.shown().unwrap().id);
+            hints.shown = None;
+        }
+        ids
+    }
+
+    #[test]
+    fn hints_seen_stay_seen() {
+        let sim = grown_up();
+        let path = std::env::temp_dir().join("belonging-test-hints.toml");
+        let _ = std::fs::remove_file(&path);
+        let mut hints = Hints::new(load_seen(&path).unwrap());
+        let all: Vec<&str> = HINTS.iter().map(|h| h.id).collect();
+        assert_eq!(show_all(&mut hints, &sim), all);
+        assert!(show_all(&mut hints, &sim).is_empty());
+
+        write_seen(&hints.seen, &path).unwrap();
+        let mut hints = Hints::new(load_seen(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert!(show_all(&mut hints, &sim).is_empty());
+    }
+
+    #[test]
+    fn every_hint_shows_again_without_the_file() {
+        let sim = grown_up();
+        let path = std::env::temp_dir().join("belonging-test-hints-missing.toml");
+        let mut seen = Seen::default();
+        seen.hints.insert("focus".to_string());
+        seen.hints.insert("plan".to_string());
+        write_seen(&seen, &path).unwrap();
+        let mut hints = Hints::new(load_seen(&path).unwrap());
+        assert_eq!(show_all(&mut hints, &sim).len(), HINTS.len() - 2);
+
+        std::fs::remove_file(&path).unwrap();
+        let mut hints = Hints::new(load_seen(&path).unwrap());
+        assert_eq!(show_all(&mut hints, &sim).len(), HINTS.len());
+    }
+}
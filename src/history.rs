@@ -0,0 +1,95 @@
+// The life week by week: every stat, the focus and the home, sampled as
+// each week ends, for the HUD's sparklines, the game over screen's charts
+// and exports. It is kept with the save. So it can't grow without bound,
+// once MAX_SAMPLES are held every other one is dropped, keeping the first
+// and last, and weeks are sampled half as often from then on; a long life
+// is kept at a coarser grain rather than cut short.
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::*;
+
+pub static MAX_SAMPLES: usize = 512;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Sample {
+    pub week: u64,
+    pub stats: [f32; 10], // in the order of STATS
+    pub focus: Focus,
+    pub home: usize, // into cities
+}
+
+impl Sample {
+    pub fn get(&self, stat: Stat) -> f32 {
+        self.stats[STATS.iter().position(|&s| s == stat).unwrap()]
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct History {
+    pub stride: u64, // weeks from one sample to the next
+    pub last_week: u64, // the last week sampled, or that would have been
+    pub samples: Vec<Sample>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self { stride: 1, last_week: 0, samples: vec![] }
+    }
+
+    // The last n values of stat, oldest first.
+    pub fn recent(&self, stat: Stat, n: usize) -> Vec<f32> {
+        let start = self.samples.len().saturating_sub(n);
+        self.samples[start..].iter().map(|s| s.get(stat)).collect()
+    }
+}
+
+// Sample the week just ended, if it's one to sample. Call once a step.
+pub fn record(sim: &mut Simulation) {
+    let week = (sim.life / TICKS_PER_WEEK) as u64;
+    let history = &mut sim.history;
+    if week <= history.last_week {
+        return;
+    }
+    history.last_week = week;
+    if week % history.stride != 0 {
+        return;
+    }
+    let mut stats = [0.0; 10];
+    for (i, &stat) in STATS.iter().enumerate() {
+        stats[i] = sim.stats.get(stat);
+    }
+    history.samples.push(Sample { week: week, stats: stats, focus: sim.current_focus,
+                                  home: sim.current_city });
+    if history.samples.len() >= MAX_SAMPLES {
+        let stride = history.stride * 2;
+        let first = history.samples[0].week;
+        history.samples.retain(|s| s.week % stride == 0 || s.week == first || s.week == week);
+        history.stride = stride;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::tests::sim;
+
+    #[test]
+    fn a_long_life_is_thinned_but_keeps_its_first_and_last_weeks() {
+        let mut sim = sim(1);
+        sim.history = History::new();
+        let mut sampled = 0;
+        for week in 1..5000 {
+            if week % sim.history.stride == 0 {
+                sampled = week;
+            }
+            sim.life = (week as f64 + 0.5) * TICKS_PER_WEEK;
+            record(&mut sim);
+            let samples = &sim.history.samples;
+            assert!(samples.len() < MAX_SAMPLES);
+            assert_eq!(samples[0].week, 1);
+            assert_eq!(samples.last().unwrap().week, sampled);
+        }
+        assert!(sim.history.stride > 1);
+        assert!(sim.history.samples.windows(2).all(|w| w[0].week < w[1].week));
+    }
+}
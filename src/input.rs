@@ -0,0 +1,338 @@
+// Keyboard and gamepad input, turned into Inputs so the rest of the game
+// doesn't care which device a press came from. Which keys and buttons mean
+// what comes from assets/bindings.toml and the player's bindings.toml; see
+// the former for the format. Game controllers can be plugged in and pulled
+// out while the game runs.
+use serde::{Deserialize, Serialize};
+use sdl2::controller::{Axis, Button};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::sdl::{self, GameController, Subsystem};
+use crate::simulation::{Action, Focus};
+
+pub static BINDINGS_PATH: &str = "bindings.toml";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Input {
+    Start,
+    Up,
+    Down,
+    Confirm,
+    Cancel,
+    Continue, // the saved life, from the title screen
+    Pause,
+    Focus(Focus),
+    Plan(Action),
+    Language,
+    MusicVolume,
+    SoundVolume,
+    Export, // the life so far, as JSON and CSV
+    Rebind,
+    Quit,
+    DebugMove,
+}
+
+// In the order the rebinding screen lists them.
+pub static INPUTS: [Input; 21] = [
+    Input::Start,
+    Input::Up,
+    Input::Down,
+    Input::Confirm,
+    Input::Cancel,
+    Input::Continue,
+    Input::Pause,
+    Input::Focus(Focus::Play),
+    Input::Focus(Focus::Socialize),
+    Input::Focus(Focus::Research),
+    Input::Focus(Focus::Create),
+    Input::Focus(Focus::Work),
+    Input::Plan(Action::Move),
+    Input::Plan(Action::Travel),
+    Input::Language,
+    Input::MusicVolume,
+    Input::SoundVolume,
+    Input::Export,
+    Input::Rebind,
+    Input::Quit,
+    Input::DebugMove,
+];
+
+impl Input {
+    pub fn name(self) -> &'static str {
+        match self {
+            Input::Start => "start",
+            Input::Up => "up",
+            Input::Down => "down",
+            Input::Confirm => "confirm",
+            Input::Cancel => "cancel",
+            Input::Continue => "continue",
+            Input::Pause => "pause",
+            Input::Focus(Focus::Play) => "focus_play",
+            Input::Focus(Focus::Socialize) => "focus_socialize",
+            Input::Focus(Focus::Research) => "focus_research",
+            Input::Focus(Focus::Create) => "focus_create",
+            Input::Focus(Focus::Work) => "focus_work",
+            Input::Plan(Action::Move) => "plan_move",
+            Input::Plan(Action::Travel) => "plan_travel",
+            Input::Language => "language",
+            Input::MusicVolume => "music_volume",
+            Input::SoundVolume => "sound_volume",
+            Input::Export => "export",
+            Input::Rebind => "rebind",
+            Input::Quit => "quit",
+            Input::DebugMove => "debug_move",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Input> {
+        INPUTS.iter().cloned().find(|i| i.name() == name)
+    }
+}
+
+// bindings.toml as written: input name -> key or button names
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BindingsFile {
+    keys: BTreeMap<String, Vec<String>>,
+    buttons: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Clone)]
+pub struct Bindings {
+    keys: Vec<(Keycode, Input)>,
+    buttons: Vec<(Button, Input)>,
+}
+
+impl Bindings {
+    pub fn key(&self, key: Keycode) -> Option<Input> {
+        self.keys.iter().find(|&&(k, _)| k == key).map(|&(_, i)| i)
+    }
+
+    pub fn button(&self, button: Button) -> Option<Input> {
+        self.buttons.iter().find(|&&(b, _)| b == button).map(|&(_, i)| i)
+    }
+
+    pub fn keys_for(&self, input: Input) -> Vec<Keycode> {
+        self.keys.iter().filter(|&&(_, i)| i == input).map(|&(k, _)| k).collect()
+    }
+
+    pub fn buttons_for(&self, input: Input) -> Vec<Button> {
+        self.buttons.iter().filter(|&&(_, i)| i == input).map(|&(b, _)| b).collect()
+    }
+
+    // Make key the only key for input, unless another input already has it,
+    // in which case that input is the error.
+    pub fn bind_key(&mut self, input: Input, key: Keycode) -> Result<(), Input> {
+        match self.key(key) {
+            Some(other) if other != input => Err(other),
+            _ => {
+                self.keys.retain(|&(_, i)| i != input);
+                self.keys.push((key, input));
+                Ok(())
+            }
+        }
+    }
+
+    pub fn bind_button(&mut self, input: Input, button: Button) -> Result<(), Input> {
+        match self.button(button) {
+            Some(other) if other != input => Err(other),
+            _ => {
+                self.buttons.retain(|&(_, i)| i != input);
+                self.buttons.push((button, input));
+                Ok(())
+            }
+        }
+    }
+
+    fn to_file(&self) -> BindingsFile {
+        let mut file = BindingsFile::default();
+        for &input in INPUTS.iter() {
+            file.keys.insert(input.name().to_string(),
+                             self.keys_for(input).iter().map(|k| k.name()).collect());
+            file.buttons.insert(input.name().to_string(),
+                                self.buttons_for(input).iter().map(|b| b.string()).collect());
+        }
+        file
+    }
+}
+
+fn parse(src: &str) -> Result<BindingsFile, String> {
+    toml::from_str(src).map_err(|e| format!("bindings: {}", e))
+}
+
+fn build(file: &BindingsFile) -> Result<Bindings, String> {
+    let mut b = Bindings { keys: vec![], buttons: vec![] };
+    for (name, keys) in file.keys.iter() {
+        let input = Input::from_name(name)
+            .ok_or_else(|| format!("bindings: unknown input '{}'", name))?;
+        for k in keys.iter() {
+            let key = Keycode::from_name(k)
+                .ok_or_else(|| format!("bindings: unknown key '{}'", k))?;
+            if let Some(other) = b.key(key) {
+                return Err(format!("bindings: key '{}' is bound to both {} and {}",
+                                   k, other.name(), name));
+            }
+            b.keys.push((key, input));
+        }
+    }
+    for (name, buttons) in file.buttons.iter() {
+        let input = Input::from_name(name)
+            .ok_or_else(|| format!("bindings: unknown input '{}'", name))?;
+        for s in buttons.iter() {
+            let button = Button::from_string(s)
+                .ok_or_else(|| format!("bindings: unknown button '{}'", s))?;
+            if let Some(other) = b.button(button) {
+                return Err(format!("bindings: button '{}' is bound to both {} and {}",
+                                   s, other.name(), name));
+            }
+            b.buttons.push((button, input));
+        }
+    }
+    Ok(b)
+}
+
+pub fn load(defaults: &str) -> Result<Bindings, String> {
+    build(&parse(defaults)?)
+}
+
+// The defaults with the player's file, if there is one, on top.
+pub fn load_with_file(defaults: &str, path: &Path) -> Result<Bindings, String> {
+    let mut file = parse(defaults)?;
+    if path.exists() {
+        let src = std::fs::read_to_string(path)
+            .map_err(|e| format!("bindings: {}: {}", path.display(), e))?;
+        let user = parse(&src).map_err(|e| format!("{} ({})", e, path.display()))?;
+        file.keys.extend(user.keys);
+        file.buttons.extend(user.buttons);
+    }
+    build(&file)
+}
+
+pub fn write(b: &Bindings, path: &Path) -> Result<(), String> {
+    let src = toml::to_string(&b.to_file()).map_err(|e| format!("bindings: {}", e))?;
+    std::fs::write(path, src).map_err(|e| format!("bindings: {}: {}", path.display(), e))
+}
+
+// The "change keys" screen: pick an input, then press the key or button
+// that should do it.
+pub struct Rebinding {
+    pub selection: usize, // into INPUTS
+    pub waiting: bool,
+    pub conflict: Option<(String, Input)>, // the key or button, and what it's bound to
+}
+
+impl Rebinding {
+    pub fn new() -> Self {
+        Self { selection: 0, waiting: false, conflict: None }
+    }
+
+    pub fn input(&self) -> Input {
+        INPUTS[self.selection]
+    }
+}
+
+// How far a stick has to be pushed to count as a press, and let go to count
+// as a release.
+static STICK_PRESS: i16 = 16000;
+static STICK_RELEASE: i16 = 8000;
+
+pub struct Controllers {
+    open: BTreeMap<i32, GameController>, // by instance id
+    stick: i32, // -1 up, 1 down, 0 centered
+}
+
+impl Controllers {
+    pub fn new() -> Self {
+        // Controllers already plugged in show up as ControllerDeviceAdded
+        // events too, so there is nothing to open here.
+        if let Err(e) = sdl::init(Subsystem::GameController) {
+            eprintln!("input: game controllers are not available ({})", e);
+        }
+        Self { open: BTreeMap::new(), stick: 0 }
+    }
+
+    pub fn handle(&mut self, bindings: &Bindings, event: &Event) -> Option<Input> {
+        match *event {
+            Event::KeyDown { keycode: Some(key), .. } => bindings.key(key),
+            Event::ControllerButtonDown { button, .. } => bindings.button(button),
+            Event::ControllerAxisMotion { axis: Axis::LeftY, value, .. } => {
+                // only the push away from the center is a press
+                let stick = if value <= -STICK_PRESS {
+                    -1
+                } else if value >= STICK_PRESS {
+                    1
+                } else if value.abs() < STICK_RELEASE {
+                    0
+                } else {
+                    self.stick
+                };
+                let pressed = stick != self.stick && stick != 0;
+                self.stick = stick;
+                match (pressed, stick) {
+                    (true, -1) => Some(Input::Up),
+                    (true, _) => Some(Input::Down),
+                    _ => None,
+                }
+            }
+            Event::ControllerDeviceAdded { which, .. } => {
+                match GameController::open(which) {
+                    Ok(c) => {
+                        self.open.insert(c.instance_id(), c);
+                    }
+                    Err(e) => eprintln!("input: could not open controller {} ({})", which, e),
+                }
+                None
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                // dropping it closes it
+                self.open.remove(&which);
+                self.stick = 0;
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static DEFAULTS: &str = include_str!("../assets/bindings.toml");
+
+    #[test]
+    fn a_key_or_button_bound_twice_is_an_error() {
+        let e = load("[keys]\nup = [\"Up\"]\ndown = [\"Up\"]\n").err().unwrap();
+        assert!(e.contains("'Up' is bound to both"), "{}", e);
+        let e = load("[buttons]\nup = [\"a\"]\ndown = [\"a\"]\n").err().unwrap();
+        assert!(e.contains("'a' is bound to both"), "{}", e);
+    }
+
+    #[test]
+    fn unknown_inputs_are_an_error() {
+        let e = load("[keys]\njump = [\"Space\"]\n").err().unwrap();
+        assert!(e.contains("unknown input 'jump'"), "{}", e);
+        assert!(load("[buttons]\njump = [\"a\"]\n").is_err());
+    }
+
+    #[test]
+    fn the_players_file_falls_back_to_the_defaults() {
+        let path = std::env::temp_dir().join("belonging-test-bindings.toml");
+        let _ = std::fs::remove_file(&path);
+        let b = load_with_file(DEFAULTS, &path).unwrap();
+        assert!(b.key(Keycode::Up) == Some(Input::Up));
+        assert!(b.button(Button::A) == Some(Input::Confirm));
+
+        // only what the file names changes
+        std::fs::write(&path, "[keys]\nup = [\"K\"]\n").unwrap();
+        let b = load_with_file(DEFAULTS, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(b.key(Keycode::K) == Some(Input::Up));
+        assert!(b.key(Keycode::Up).is_none());
+        assert!(b.key(Keycode::Down) == Some(Input::Down));
+        assert!(b.button(Button::DPadUp) == Some(Input::Up));
+    }
+}
@@ -0,0 +1,179 @@
+// Children. A kid is had by picking a `kid` choice in a kids event, and
+// lives at home until leave_age, needing the time the parent spends
+// socializing: time with the kids is time not spent making or keeping
+// friends. Kids at home give purpose, and cost relaxation when neglected;
+// the bond built while they're young adds to belonging for the rest of the
+// life. Events with a kid_age are each kid's milestones. The rates are in
+// assets/rules.toml.
+use crate::events;
+use crate::simulation::*;
+
+pub fn born(sim: &mut Simulation) {
+    sim.kids.push(Kid {
+        weeks: 0.0,
+        need: 0.0,
+        bond: 0.0,
+        home: true,
+        milestone: 0,
+    });
+}
+
+pub fn at_home(sim: &Simulation) -> usize {
+    sim.kids.iter().filter(|k| k.home).count()
+}
+
+// The share of socializing that's left for friends.
+pub fn free_time(sim: &Simulation) -> f32 {
+    (1.0 - sim.rules.kids.time * at_home(sim) as f32).max(0.0)
+}
+
+// Apply one frame's worth (dweek weeks) of raising the kids.
+pub fn apply(sim: &mut Simulation, dweek: f32) {
+    let rules = sim.rules.kids.clone();
+    let socializing = sim.current_focus == Focus::Socialize;
+    let share = 1.0 / at_home(sim).max(1) as f32;
+
+    for kid in sim.kids.iter_mut() {
+        kid.weeks += dweek;
+        if kid.home && kid.age() >= rules.leave_age {
+            kid.home = false;
+            kid.need = 0.0;
+        }
+        if kid.home {
+            if socializing {
+                kid.need -= rules.care * share * dweek;
+                kid.bond += rules.bond * share * dweek;
+            } else {
+                kid.need += rules.need * dweek;
+            }
+            kid.need = kid.need.max(0.0).min(1.0);
+            kid.bond = (kid.bond - rules.neglect * kid.need * dweek).max(0.0).min(1.0);
+            sim.stats.purpose += rules.purpose * dweek;
+            sim.stats.relaxation -= rules.stress * kid.need * dweek;
+        }
+        sim.stats.belonging += rules.belonging * kid.bond * dweek;
+    }
+
+    start_milestone(sim);
+}
+
+// Offer the next milestone a kid has reached, one at a time.
+fn start_milestone(sim: &mut Simulation) {
+    match sim.game_state {
+        GameState::Game => {}
+        _ => return,
+    }
+    for k in 0..sim.kids.len() {
+        let age = sim.kids[k].age();
+        while sim.kids[k].milestone < age {
+            sim.kids[k].milestone += 1;
+            let milestone = sim.kids[k].milestone;
+            let ev = sim.events.list.iter()
+                .find(|ev| ev.kid_age == Some(milestone)).cloned();
+            if let Some(ev) = ev {
+                if events::conditions_met(sim, &ev.require) {
+                    start_modal(sim, &ev);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::friends;
+    use crate::simulation::tests::sim;
+
+    fn parent(kids: usize, focus: Focus) -> Simulation {
+        let mut sim = sim(1);
+        sim.kids.clear();
+        for _ in 0..kids {
+            born(&mut sim);
+        }
+        sim.current_focus = focus;
+        sim
+    }
+
+    #[test]
+    fn kids_left_alone_need_you_and_the_bond_suffers() {
+        let mut sim = parent(1, Focus::Work);
+        let rules = sim.rules.kids.clone();
+        sim.kids[0].bond = 0.5;
+        sim.stats.relaxation = 0.5;
+        let purpose = sim.stats.purpose;
+        apply(&mut sim, 1.0);
+        let kid = &sim.kids[0];
+        assert!((kid.need - rules.need).abs() < 1e-6);
+        assert!((kid.bond - (0.5 - rules.neglect * rules.need)).abs() < 1e-6);
+        assert!((sim.stats.relaxation - (0.5 - rules.stress * rules.need)).abs() < 1e-6);
+        assert!((sim.stats.purpose - (purpose + rules.purpose)).abs() < 1e-6);
+
+        // need tops out at 1
+        for _ in 0..200 {
+            apply(&mut sim, 1.0);
+        }
+        assert_eq!(sim.kids[0].need, 1.0);
+        assert_eq!(sim.kids[0].bond, 0.0);
+    }
+
+    #[test]
+    fn care_is_shared_between_the_kids_at_home() {
+        let mut sim = parent(2, Focus::Socialize);
+        let rules = sim.rules.kids.clone();
+        for kid in sim.kids.iter_mut() {
+            kid.need = 0.5;
+        }
+        apply(&mut sim, 1.0);
+        for kid in sim.kids.iter() {
+            assert!((kid.need - (0.5 - rules.care / 2.0)).abs() < 1e-6);
+            let bond = rules.bond / 2.0 - rules.neglect * kid.need;
+            assert!((kid.bond - bond).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn kids_leave_home_at_leave_age() {
+        let mut sim = parent(1, Focus::Work);
+        let leave_age = sim.rules.kids.leave_age;
+        sim.kids[0].weeks = leave_age as f32 * 50.0 - 0.5;
+        sim.kids[0].milestone = leave_age;
+        sim.kids[0].need = 0.5;
+        sim.kids[0].bond = 0.5;
+        apply(&mut sim, 1.0);
+        assert!(!sim.kids[0].home);
+        assert_eq!(sim.kids[0].need, 0.0);
+        assert_eq!(at_home(&sim), 0);
+
+        // grown kids still add to belonging, and no longer need you
+        let belonging = sim.stats.belonging;
+        apply(&mut sim, 1.0);
+        assert_eq!(sim.kids[0].need, 0.0);
+        assert_eq!(sim.kids[0].bond, 0.5);
+        let expected = belonging + sim.rules.kids.belonging * 0.5;
+        assert!((sim.stats.belonging - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn kids_at_home_take_time_from_friends() {
+        let time = sim(1).rules.kids.time;
+        for kids in 0..6 {
+            let sim = parent(kids, Focus::Socialize);
+            let free = (1.0 - time * kids as f32).max(0.0);
+            assert_eq!(free_time(&sim), free);
+        }
+
+        let mut sim = parent(2, Focus::Socialize);
+        sim.rules.friends.meet = 0.0;
+        let home = sim.current_city;
+        sim.cities[home].friends = vec![Friend {
+            bond: 0.5,
+            compatibility: 1.0,
+            exp: 0.0,
+        }];
+        friends::apply(&mut sim, 1.0);
+        let grown = sim.rules.friends.grow * (1.0 - 2.0 * time);
+        assert!((sim.cities[home].friends[0].bond - (0.5 + grown)).abs() < 1e-6);
+    }
+}
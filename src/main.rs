@@ -1,438 +1,451 @@
+mod audio;
+mod bdf;
+mod events;
+mod export;
+mod font;
+mod friends;
+mod hints;
+mod history;
+mod input;
+mod kids;
+mod money;
+mod psf;
+mod region;
+mod replay;
+mod rng;
+mod roots;
+mod rules;
+mod save;
+mod sdl;
+mod simulation;
+mod strings;
+mod summary;
+mod synth;
+mod trips;
+
 use rockwork::context::Context;
 use rockwork::mesh::Mesh;
 use rockwork::program::Program;
 use rockwork::texture::Texture;
 use rockwork::framebuffer::Framebuffer;
-use sdl2::event::Event;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Keycode;
-use std::cell::RefCell;
+use sdl2::mouse::MouseButton;
 use std::io::Cursor;
-use std::io::Error;
-use std::time::Duration;
+use std::path::Path;
+use std::time::{Duration, Instant};
 use gl::types::*;
 use nalgebra::{zero, Vector2, Vector4, Matrix2};
 use nalgebra::geometry::Point2;
-use std::sync::Mutex;
-
-#[macro_use]
-extern crate lazy_static;
-
-pub enum GameState {
-    Title,
-    Instruction,
-    Game,
-    Modal,
-    Fly,
-    GameOver,
-}
 
-pub struct Modal {
-    kind: ModalKind,
-    text: &'static str,
-    choices: Vec<&'static str>,
-    selection: i32,
-}
+use crate::audio::Audio;
+use crate::font::{Align, Font};
+use crate::hints::Hints;
+use crate::input::{Bindings, Controllers, Input, Rebinding};
+use crate::replay::{Playback, Replay};
+use crate::simulation::*;
+use crate::strings::Strings;
 
-impl Modal {
-    pub fn new(k: ModalKind, text: &'static str, choices: Vec<&'static str>) -> Self {
-        Self { kind: k, text: text, choices: choices, selection: 0 }
-    }
-}
+pub struct GameData {
+    program: Program,
+    water: Program,
+    water_tex: Texture,
+    quad: Mesh,
+    map: Texture,
+    cursor: Texture,
+    cursor_position: Point2<i32>, // in game pixels, not window pixels
+    window_size: Vector2<u32>,
+    controllers: Controllers,
+    bindings: Bindings,
+    rebinding: Option<Rebinding>,
+    log_scroll: usize, // into the game over screen's list of choices
+    paused: bool,
+    strings: Strings,
+    audio: Audio,
+    volume_shown: Option<Instant>, // when the volume last changed
+    exported: Option<Instant>, // when the life was last exported
+    hints: Hints,
+    city_marker: Texture,
+    friend: Texture,
+    dot: Texture, // a white pixel, stretched to draw rectangles
+    home_marker: Texture,
+    bar: Texture,
+    bar_base: Texture,
+    belonging_label: Texture,
+    purpose_label: Texture,
+    pride_label: Texture,
+    relaxation_label: Texture,
+    focus_labels: Texture,
+    focus_box: Texture,
+    font: Texture,
+    glyphs: Font, // where each character is in font
+    age_label: Texture,
+    arrow: Texture,
+    modal_box: Texture,
+    title: Texture,
+    plane: Texture,
+    fb: Framebuffer,
+    color_tex: Texture,
+    light_tex: Texture,
 
-pub enum ModalKind {
-    Tantrum,
-    Move,
-    Married,
-    Divorce,
-    Kids,
-    Die,
+    tick: f64, // simulated time, including the part of a step not yet taken
+    arrow_position: Vector2<f32>,
+    prev_arrow_position: Vector2<f32>,
+    prev_plane_position: Vector2<f32>,
+    has_save: bool,
+    recording: Replay,
+    playback: Option<Playback>,
+    sim: Simulation,
 }
 
-pub fn maybe_start_modal(gd: &mut GameData, new_modal: Modal) -> bool {
-    for m in gd.modals_done.iter() {
-        if str_eq(m.text, new_modal.text) {
-            return false;
+
+static mut GAME_DATA: Option<GameData> = None;
+static WIDTH: usize = 320;
+static HEIGHT: usize = 240;
+static SCALING: usize = 3;
+static FOCUS_BOX: [i32; 2] = [265, 40]; // center of the focus box
+static MODAL_TEXT_WIDTH: i32 = 110;
+static VOLUME_SHOWN_SECS: u64 = 2;
+static EXPORTED_SHOWN_SECS: u64 = 3;
+// Every locale's string table; see assets/strings/en.toml.
+static LOCALES: [(&str, &str); 2] = [
+    ("en", include_str!("../assets/strings/en.toml")),
+    ("fr", include_str!("../assets/strings/fr.toml")),
+];
+
+// Save the life so far, unless it is over or hasn't started, and the
+// session's replay, and exit. Nothing is written while playing a replay back.
+fn quit(gd: &GameData) -> ! {
+    if gd.playback.is_some() {
+        std::process::exit(0);
+    }
+    if let Err(e) = replay::write(&gd.recording, Path::new(replay::REPLAY_PATH)) {
+        eprintln!("{}", e);
+    }
+    match gd.sim.game_state {
+        GameState::Title => {}
+        GameState::GameOver => {
+            let _ = std::fs::remove_file(save::SAVE_PATH);
+        }
+        _ => {
+            if let Err(e) = save::write(&gd.sim, Path::new(save::SAVE_PATH)) {
+                eprintln!("{}", e);
+            }
         }
     }
-    gd.current_modal = Some(new_modal);
-    gd.game_state = GameState::Modal;
-    return true;
-}
-
-// idea? everything starts negative?
-pub struct Stats {
-    money: f32, // Debt
-    belonging: f32, // Loneliness
-    purpose: f32, // Ennui
-    pride: f32, // Shame
-    relaxation: f32,  // Stress 
-
-    play_exp: f32,
-    social_exp: f32,
-    research_exp: f32,
-    create_exp: f32,
-    work_exp: f32,
+    std::process::exit(0);
 }
 
-impl Stats {
-    fn new() -> Self {
-        Self {
-            money: 0.0,
-            belonging: 1.0,
-            purpose: 1.0,
-            pride: 1.0,
-            relaxation: 1.0,
-
-            play_exp: 0.0,
-            social_exp: 0.0,
-            research_exp: 0.0,
-            create_exp: 0.0,
-            work_exp: 0.0,
+fn continue_life(gd: &mut GameData) {
+    let events = gd.sim.events.clone();
+    let rules = gd.sim.rules.clone();
+    match save::read(Path::new(save::SAVE_PATH), events, rules) {
+        Ok(sim) => {
+            gd.sim = sim;
+            gd.audio.reset(&gd.sim);
+            // a replay has to start from the loaded life
+            match Replay::new(&gd.sim) {
+                Ok(r) => gd.recording = r,
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            gd.has_save = false;
         }
     }
 }
 
-pub enum Focus {
-    Play,
-    Socialize,
-    Research,
-    Create,
-    Work,
+// Where the arrow points for each focus, relative to the top of the screen.
+fn focus_row(f: Focus) -> i32 {
+    17 + match f {
+        Focus::Play => 0,
+        Focus::Socialize => 11,
+        Focus::Research => 22,
+        Focus::Create => 33,
+        Focus::Work => 45,
+    }
 }
 
-pub fn focus_stage(gd: &GameData) -> u32 {
-    match gd.age {
-        0 => 0,
-        1...7 => 1,
-        8...14 => 2,
-        15...21 => 3,
-        _ => 4,
+fn focus_at(gd: &GameData, p: Point2<i32>) -> Option<Focus> {
+    if (p.x - FOCUS_BOX[0]).abs() > gd.focus_box.width as i32 / 2 {
+        return None;
     }
+    [Focus::Play, Focus::Socialize, Focus::Research, Focus::Create, Focus::Work].iter()
+        .cloned()
+        .find(|&f| (p.y - focus_row(f)).abs() <= 5)
 }
 
-pub fn focus_is_unlocked(gd: &GameData, f: Focus) -> bool {
-    match f {
-        Focus::Play => true,
-        Focus::Socialize => gd.age >= 1,
-        Focus::Research => gd.age >= 8,
-        Focus::Create => gd.age >= 15,
-        Focus::Work => gd.age >= 22
+// The modal choice under p, laid out as draw_modal draws them.
+fn choice_at(gd: &GameData, m: &Modal, p: Point2<i32>) -> Option<i32> {
+    for (i, c) in m.choices.iter().enumerate() {
+        let y = HEIGHT as i32 / 2 + 10 * i as i32;
+        let half_w = gd.glyphs.width(&gd.strings.choice(&gd.sim, m, c)) / 2 + 3;
+        if (p.y - y).abs() <= 5 && (p.x - WIDTH as i32 / 2).abs() <= half_w {
+            return Some(i as i32);
+        }
     }
+    None
 }
 
-pub enum Action {
-    Move,
-    Travel,
+fn city_at(sim: &Simulation, p: Point2<i32>) -> Option<usize> {
+    sim.cities.iter().position(|c| {
+        (p.x - c.position[0]).abs() <= 4 && (p.y - c.position[1]).abs() <= 4
+    })
 }
 
-#[derive(Clone)]
-pub struct Friend {
-    bond: f32,
-    compatibility: f32,
-    exp: f32, // time spent with friend
-    //name: &'static str,
+// The window can be resized, so this can't just divide by SCALING.
+fn to_game_pixels(gd: &GameData, x: i32, y: i32) -> Point2<i32> {
+    Point2::new(x * WIDTH as i32 / gd.window_size.x.max(1) as i32,
+                y * HEIGHT as i32 / gd.window_size.y.max(1) as i32)
 }
 
-#[derive(Clone)]
-pub struct City {
-    name: &'static str,
-    position: [i32; 2],
-    friends: Vec<Friend>,
-    home: bool,
-    exp: f32, // time spent here
+fn click(gd: &GameData, p: Point2<i32>) -> Vec<Command> {
+    match gd.sim.game_state {
+        GameState::Title => vec![Command::Start],
+        GameState::Instruction | GameState::GameOver => vec![Command::Confirm],
+        GameState::Modal => {
+            let m = gd.sim.current_modal.as_ref().unwrap();
+            let mut choice = choice_at(gd, m, p);
+            // a move can also be picked by clicking its city on the map
+            if let (None, ModalKind::Move) = (choice, m.kind) {
+                if let Some(city) = city_at(&gd.sim, p) {
+                    let name = &gd.sim.cities[city].name;
+                    choice = m.choices.iter()
+                        .position(|c| c.city.as_ref().map_or(false, |city| str_eq(city, name)))
+                        .map(|i| i as i32);
+                }
+            }
+            match choice {
+                Some(i) => vec![Command::Select(i), Command::Confirm],
+                None => vec![],
+            }
+        }
+        GameState::Game => match focus_at(gd, p) {
+            Some(f) => vec![Command::SetFocus(f)],
+            None => vec![],
+        },
+        // the first click on a city picks it, the second goes
+        GameState::Plan => match city_at(&gd.sim, p) {
+            Some(i) if gd.sim.plan.as_ref().map_or(false, |plan| plan.city == i) => {
+                vec![Command::Confirm]
+            }
+            Some(i) => vec![Command::Select(i as i32)],
+            None => vec![],
+        },
+        _ => vec![],
+    }
 }
 
-impl City {
-    pub fn new(name: &'static str, position: [i32; 2]) -> Self {
-        City { name: name, position: position, home: false, friends: vec![], exp: 0.0 }
-    }
-    pub fn new_home(name: &'static str, position: [i32; 2]) -> Self {
-        City { name: name, position: position, home: true, friends: vec![], exp: 0.0 }
+// What an Input means right now. Most Inputs are passed straight on as
+// Commands, which the simulation ignores when they don't apply.
+fn input_commands(gd: &mut GameData, input: Input) -> Vec<Command> {
+    match (&gd.sim.game_state, input) {
+        (_, Input::Quit) => quit(gd),
+        (GameState::Title, Input::Continue) => {
+            if gd.has_save && gd.playback.is_none() {
+                continue_life(gd);
+            }
+            vec![]
+        }
+        (GameState::Title, Input::Confirm) |
+        (GameState::Title, Input::Pause) => vec![Command::Start],
+        (GameState::Title, Input::Rebind) => {
+            gd.rebinding = Some(Rebinding::new());
+            vec![]
+        }
+        (_, Input::Pause) => {
+            gd.paused = !gd.paused;
+            vec![]
+        }
+        (_, Input::Language) => {
+            next_locale(gd);
+            vec![]
+        }
+        (GameState::Title, Input::Export) | (GameState::Instruction, Input::Export) => vec![],
+        (_, Input::Export) => {
+            export_life(gd);
+            vec![]
+        }
+        (_, Input::MusicVolume) | (_, Input::SoundVolume) => {
+            change_volume(gd, input);
+            vec![]
+        }
+        // backing out of a modal highlights its last choice, which declines
+        (GameState::Modal, Input::Cancel) => {
+            let m = gd.sim.current_modal.as_ref().unwrap();
+            vec![Command::Select(m.choices.len() as i32 - 1)]
+        }
+        (GameState::GameOver, Input::Up) => {
+            gd.log_scroll = gd.log_scroll.saturating_sub(1);
+            vec![]
+        }
+        (GameState::GameOver, Input::Down) => {
+            gd.log_scroll += 1;
+            vec![]
+        }
+        (GameState::GameOver, Input::Cancel) |
+        (GameState::Plan, Input::Cancel) |
+        (GameState::Instruction, Input::Cancel) => vec![Command::Cancel],
+        (_, Input::Start) => vec![Command::Start],
+        (_, Input::Up) => vec![Command::Up],
+        (_, Input::Down) => vec![Command::Down],
+        (_, Input::Confirm) => vec![Command::Confirm],
+        (_, Input::Focus(f)) => vec![Command::SetFocus(f)],
+        (_, Input::Plan(a)) => vec![Command::Plan(a)],
+        (_, Input::DebugMove) => vec![Command::DebugMove],
+        (_, Input::Cancel) | (_, Input::Continue) | (_, Input::Rebind) => vec![],
     }
 }
 
-lazy_static!{
-    static ref CITIES: Mutex<Vec<City>> = {
-        let mut v = Vec::new();
-        let cities = [
-            City::new_home("Toronto", [181, 135]),
-            City::new("Ottawa", [183, 123]),
-            City::new("Montreal", [196, 122]),
-            City::new("SF", [19, 165]),
-            City::new("Seattle", [31, 117]),
-            City::new("Vancouver", [32, 103]),
-            City::new("LA", [29, 183]),
-            City::new("Las Vegas", [48, 173]),
-            City::new("Calgary", [77, 106]),
-            City::new("Miami", [196, 223]),
-            City::new("Chicago", [149, 149]),
-            City::new("NY", [204, 145]),
-            City::new("Halifax", [232, 115]),
-            City::new("Boulder", [92, 166]),
-        ];
-        v.extend_from_slice(&cities);
-        Mutex::new(v)
-    };
+fn next_locale(gd: &mut GameData) {
+    let current = LOCALES.iter().position(|&(l, _)| l == gd.strings.locale).unwrap_or(0);
+    let (locale, src) = LOCALES[(current + 1) % LOCALES.len()];
+    match strings::parse(src) {
+        Ok(table) => gd.strings.set_locale(locale, table),
+        Err(e) => eprintln!("{} ({})", e, locale),
+    }
 }
 
-pub fn str_eq(s1: &'static str, s2: &'static str) -> bool {
-    return s1.to_string().to_lowercase() == s2.to_string().to_lowercase();
+// Turn the music or the sounds down a step, and remember it for next time.
+fn change_volume(gd: &mut GameData, input: Input) {
+    let mut volume = gd.audio.volume();
+    match input {
+        Input::MusicVolume => volume.music = audio::step_down(volume.music),
+        _ => volume.sound = audio::step_down(volume.sound),
+    }
+    gd.audio.set_volume(volume);
+    gd.volume_shown = Some(Instant::now());
+    if let Err(e) = audio::write_volume(&volume, Path::new(audio::VOLUME_PATH)) {
+        eprintln!("{}", e);
+    }
 }
 
-
-pub fn home_city() -> City {
-    for c in CITIES.lock().unwrap().iter() {
-        if c.home {
-            return c.clone();
-        }
+// Write the life so far next to the game, for the player or for balancing.
+fn export_life(gd: &mut GameData) {
+    match export::write(&gd.sim, Path::new(".")) {
+        Ok(()) => gd.exported = Some(Instant::now()),
+        Err(e) => eprintln!("{}", e),
     }
-    panic!("no home city?");
 }
 
-pub fn set_home_city(new_home: &'static str) {
-    let mut cities =  CITIES.lock().unwrap();
-    for c in cities.iter_mut() {
-        if c.home {
-            c.home = false;
+// Events while the "change keys" screen is up. Escape always stops waiting
+// for a key, so a bad binding can't leave the screen stuck.
+fn rebind_event(gd: &mut GameData, event: &Event) {
+    let input = gd.controllers.handle(&gd.bindings, event);
+    let r = gd.rebinding.as_mut().unwrap();
+    if r.waiting {
+        let (name, bound) = match *event {
+            Event::KeyDown { keycode: Some(Keycode::Escape), .. } => (String::new(), Ok(())),
+            Event::KeyDown { keycode: Some(key), .. } => {
+                (key.name(), gd.bindings.bind_key(r.input(), key))
+            }
+            Event::ControllerButtonDown { button, .. } => {
+                (button.string(), gd.bindings.bind_button(r.input(), button))
+            }
+            _ => return,
+        };
+        r.waiting = false;
+        r.conflict = bound.err().map(|other| (name.to_lowercase(), other));
+        return;
+    }
+    match input {
+        Some(Input::Up) => {
+            r.selection = (r.selection + input::INPUTS.len() - 1) % input::INPUTS.len();
+        }
+        Some(Input::Down) => r.selection = (r.selection + 1) % input::INPUTS.len(),
+        Some(Input::Confirm) => {
+            r.waiting = true;
+            r.conflict = None;
         }
-        if c.name.to_string().to_lowercase() == new_home.to_string().to_lowercase() {
-            c.home = true;
+        Some(Input::Cancel) | Some(Input::Quit) => {
+            gd.rebinding = None;
+            if let Err(e) = input::write(&gd.bindings, Path::new(input::BINDINGS_PATH)) {
+                eprintln!("{}", e);
+            }
         }
+        _ => {}
     }
 }
 
-pub struct GameData {
-    program: Program,
-    water: Program,
-    water_tex: Texture,
-    quad: Mesh,
-    map: Texture,
-    cursor: Texture,
-    cursor_position: Point2<i32>,
-    city_marker: Texture,
-    home_marker: Texture,
-    bar: Texture,
-    bar_base: Texture,
-    belonging_label: Texture,
-    purpose_label: Texture,
-    pride_label: Texture,
-    relaxation_label: Texture,
-    focus_labels: Texture,
-    focus_box: Texture,
-    numbers: Texture,
-    font: Texture,
-    age_label: Texture,
-    arrow: Texture,
-    modal_box: Texture,
-    title: Texture,
-    plane: Texture,
-    fb: Framebuffer,
-    color_tex: Texture,
-    light_tex: Texture,
-    tick: f64,
-
-    age: u32,
-    stats: Stats,
-    current_focus: Focus,
-    current_city: usize,
-    married: bool,
-    kids: u32,
-    moves: u32,
-    arrow_position: Vector2<f32>,
-    plane_position: Vector2<f32>,
-    current_modal: Option<Modal>,
-    modals_done: Vec<Modal>,
-    game_state: GameState,
-}
-
-
-static mut GAME_DATA: Option<GameData> = None;
-static WIDTH: usize = 320;
-static HEIGHT: usize = 240;
-static SCALING: usize = 3;
-//static TICKS_PER_WEEK: f64 = 0.1;
-static TICKS_PER_WEEK: f64 = 0.06;
-
-fn handle_input(ctx: &mut Context) {
+fn handle_input(ctx: &mut Context) -> Vec<Command> {
     let gd = unsafe { GAME_DATA.as_mut().unwrap() };
-    match gd.game_state {
-        GameState::Title => {
-                for event in ctx.sdl_event_pump.poll_iter() {
-                    match event {
-                    Event::Quit { .. } |
-                        Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
-                        std::process::exit(0);
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::Space), .. } => {
-                        gd.game_state = GameState::Game;
-                        gd.tick = 0.0;
-                    }
-                    _ => {}
-                }
+    let mut commands = vec![];
+    for event in ctx.sdl_event_pump.poll_iter() {
+        if gd.rebinding.is_some() {
+            match event {
+                Event::Quit { .. } => quit(gd),
+                _ => rebind_event(gd, &event),
             }
+            continue;
         }
-        GameState::Modal => {
-            for event in ctx.sdl_event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. } |
-                        Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
-                        std::process::exit(0);
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::Up), .. } => {
-                        let mut mo = gd.current_modal.as_mut().unwrap();
-                        mo.selection -= 1; 
-                        if mo.selection < 0 {
-                            mo.selection = mo.choices.len() as i32 - 1;
-                        }
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::Down), .. } => {
-                        let mut mo = gd.current_modal.as_mut().unwrap();
-                        mo.selection += 1; 
-                        mo.selection %= mo.choices.len() as i32;
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::Return), .. } => {
-                        execute_modal(gd);
-                    }
-                    _ => {}
-                }
-            }
+        if let Some(input) = gd.controllers.handle(&gd.bindings, &event) {
+            commands.extend(input_commands(gd, input));
+            continue;
         }
-        GameState::Game => {
-            for event in ctx.sdl_event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. } |
-                        Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
-                        std::process::exit(0);
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::Up), .. } => {
-                        match gd.current_focus {
-                            Focus::Work => {
-                                gd.current_focus = Focus::Create;
-                            }
-                            Focus::Create => {
-                                gd.current_focus = Focus::Research;
-                            }
-                            Focus::Research => {
-                                gd.current_focus = Focus::Socialize;
-                            }
-                            _ => {
-                                gd.current_focus = Focus::Play;
-                            }
-                        }
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::Down), .. } => {
-                        match gd.current_focus {
-                            Focus::Play => {
-                                if focus_is_unlocked(gd, Focus::Socialize) {
-                                    gd.current_focus = Focus::Socialize;
-                                }
-                            }
-                            Focus::Socialize => {
-                                if focus_is_unlocked(gd, Focus::Research) {
-                                    gd.current_focus = Focus::Research;
-                                }
-                            }
-                            Focus::Research => {
-                                if focus_is_unlocked(gd, Focus::Create) {
-                                    gd.current_focus = Focus::Create;
-                                }
-                            }
-                            _ => {
-                                if focus_is_unlocked(gd, Focus::Work) {
-                                    gd.current_focus = Focus::Work;
-                                }
-                            }
-                        }
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::P), .. } => {
-                        gd.current_focus = Focus::Play;
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::S), .. } => {
-                        if focus_is_unlocked(gd, Focus::Socialize) {
-                            gd.current_focus = Focus::Socialize;
-                        }
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::T), .. } => {
-                        if focus_is_unlocked(gd, Focus::Research) {
-                            gd.current_focus = Focus::Research;
-                        }
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::C), .. } => {
-                        if focus_is_unlocked(gd, Focus::Create) {
-                            gd.current_focus = Focus::Create;
-                        }
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::W), .. } => {
-                        if focus_is_unlocked(gd, Focus::Work) {
-                            gd.current_focus = Focus::Work;
-                        }
-                    }
-                    Event::KeyDown { keycode: Some(Keycode::L), .. } => {
-                        gd.current_modal = Some(Modal::new(ModalKind::Move,
-                                                           "goto university?",
-                                                           vec!["Toronto", 
-                                                           "SF", "Montreal", "No"]));
-                        gd.game_state = GameState::Modal;
-                    }
-                    /*
-                    Event::MouseMotion { x, y, .. } => {
-                        //gd.cursor_position = Point2::new(x, y);
-                    } */
-                    _ => {}
-                }
+        let cmd = match event {
+            Event::Quit { .. } => {
+                quit(gd);
             }
-        }
-        _ => {
-            for event in ctx.sdl_event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. } |
-                        Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
-                        std::process::exit(0);
+            Event::MouseMotion { x, y, .. } => {
+                gd.cursor_position = to_game_pixels(gd, x, y);
+                if let Some(m) = &gd.sim.current_modal {
+                    match choice_at(gd, m, gd.cursor_position) {
+                        Some(i) if i != m.selection => Command::Select(i),
+                        _ => continue,
                     }
-                    _ => {}
+                } else {
+                    continue;
                 }
             }
-        }
+            Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+                gd.cursor_position = to_game_pixels(gd, x, y);
+                commands.extend(click(gd, gd.cursor_position));
+                continue;
+            }
+            Event::Window { win_event: WindowEvent::SizeChanged(w, h), .. } => {
+                gd.window_size = Vector2::new(w as u32, h as u32);
+                continue;
+            }
+            _ => continue,
+        };
+        commands.push(cmd);
     }
+    commands
 }
 
-fn draw_digit(gd: &GameData, p: Point2<i32>, digit: u32) {
-    assert!(digit < 10);
-    let new_p = Point2::new(p.x + 23 - (digit * 5) as i32, p.y);
-    draw_texture_rect_extra(gd, &gd.numbers, new_p,
-                            gd.tick as f32, 
-                            Vector2::new(0.1 * digit as f32 + 0.1, 1.0),
-                            Vector2::new(1.0 - 0.1 * digit as f32, 1.0),
-                            Vector2::new(0.0, 0.0), 
+// Draw one character with the left edge of its cell at p.x and the middle at
+// p.y.
+fn draw_glyph(gd: &GameData, p: Point2<i32>, c: char) {
+    let g = gd.glyphs.glyph(c);
+    let (cw, ch) = (gd.glyphs.cell[0], gd.glyphs.cell[1]);
+    let (w, h) = (gd.font.width as f32, gd.font.height as f32);
+    let new_p = Point2::new(p.x + gd.font.width as i32 / 2 - (g[0] * cw) as i32,
+                            p.y + gd.font.height as i32 / 2 - (g[1] * ch + ch / 2) as i32);
+    draw_texture_rect_extra(gd, &gd.font, new_p,
+                            gd.tick as f32,
+                            Vector2::new(((g[0] + 1) * cw) as f32 / w,
+                                         ((g[1] + 1) * ch) as f32 / h),
+                            Vector2::new(1.0 - (g[0] * cw) as f32 / w,
+                                         1.0 - (g[1] * ch) as f32 / h),
+                            Vector2::new(0.0, 0.0),
                             Vector4::new(1.0, 1.0, 1.0, 1.0));
 }
 
-fn draw_letter(gd: &GameData, p: Point2<i32>, letter: char) {
-    let index = if letter == '?' { 26 } else {
-        let c = letter.to_ascii_uppercase();
-        c as i32 - 'A' as i32
-    };
-    let new_p = Point2::new(p.x + 81 - (index * 6) as i32, p.y);
-    let letter_w = 1.0 / 27.0;
+fn draw_lines(gd: &GameData, p: Point2<i32>, lines: &[String], align: Align) {
+    for (q, c) in gd.glyphs.layout(lines, [p.x, p.y], align) {
+        draw_glyph(gd, Point2::new(q[0], q[1]), c);
+    }
+}
 
-    draw_texture_rect_extra(gd, &gd.font, new_p,
-                            gd.tick as f32, 
-                            Vector2::new(letter_w * index as f32 + letter_w, 1.0),
-                            Vector2::new(1.0 - letter_w * index as f32, 1.0),
-                            Vector2::new(0.0, 0.0), 
-                            Vector4::new(1.0, 1.0, 1.0, 1.0));
+// p is the middle of the first line, on the side given by align.
+fn draw_string(gd: &GameData, p: Point2<i32>, s: &str, align: Align) {
+    let lines: Vec<String> = s.lines().map(|l| l.to_string()).collect();
+    draw_lines(gd, p, &lines, align);
 }
 
-fn draw_string(gd: &GameData, p: Point2<i32>, s: String) {
-    let mut y = p.y;
-    for l in s.lines() {
-        let len = l.len();
-        for (i, c) in l.chars().enumerate() {
-            draw_letter(gd, Point2::new(p.x + 6 * i as i32 
-                                        - 3 * len as i32, y), c);
-        }
-        y += 10;
-    }
+fn draw_string_wrapped(gd: &GameData, p: Point2<i32>, s: &str, align: Align, width: i32) {
+    draw_lines(gd, p, &gd.glyphs.wrap(s, width), align);
 }
 
 fn draw_texture_rect_centered(gd: &GameData, tex: &Texture, p: Point2<i32>) {
@@ -513,7 +526,54 @@ fn draw_water(gd: &GameData) {
     gd.water.draw(&gd.quad);
 }
 
-fn draw_bar(gd: &GameData, p: Point2<i32>, label: &Texture, value: f32) {
+// A filled rectangle, corners included.
+fn draw_rect(gd: &GameData, top_left: Point2<i32>, bottom_right: Point2<i32>,
+             tint: Vector4<f32>) {
+    let w = (bottom_right.x - top_left.x + 1) as f32;
+    let h = (bottom_right.y - top_left.y + 1) as f32;
+    let mat = Matrix2::new(w / WIDTH as f32, 0.0,
+                           0.0, h / HEIGHT as f32);
+    let center = Point2::new((top_left.x + bottom_right.x + 1) / 2,
+                             (top_left.y + bottom_right.y + 1) / 2);
+    draw_texture_rect_with_mat2(gd, &gd.dot, center, gd.tick as f32,
+                                Vector2::new(1.0, 1.0), // trim
+                                Vector2::new(1.0, 1.0), // rtrim
+                                zero(), tint, mat);
+}
+
+// values (0 to 1) as a line, left to right, filling a box of size with its
+// bottom left at p; the values are stretched or squeezed to fit.
+fn draw_chart(gd: &GameData, p: Point2<i32>, size: Vector2<i32>, values: &[f32],
+              tint: Vector4<f32>) {
+    if values.is_empty() {
+        return;
+    }
+    let mut prev = None;
+    for x in 0..size.x as usize {
+        let v = values[x * values.len() / size.x as usize].max(0.0).min(1.0);
+        let y = p.y - (v * (size.y - 1) as f32).round() as i32;
+        // joined to the last column, so steep changes stay a line
+        let (top, bottom) = match prev {
+            Some(py) => (y.min(py), y.max(py)),
+            None => (y, y),
+        };
+        draw_rect(gd, Point2::new(p.x + x as i32, top), Point2::new(p.x + x as i32, bottom), tint);
+        prev = Some(y);
+    }
+}
+
+static SPARKLINE: [i32; 2] = [24, 12]; // size, left of each bar
+static CHART: [i32; 2] = [64, 24]; // size of each chart on the game over screen
+
+fn draw_bar(gd: &GameData, p: Point2<i32>, label: &Texture, stat: Stat) {
+    let value = gd.sim.stats.get(stat);
+    // how it's been going lately
+    let recent = gd.sim.history.recent(stat, SPARKLINE[0] as usize);
+    draw_chart(gd, Point2::new(p.x - gd.bar_base.width as i32 / 2 - 4 - SPARKLINE[0],
+                               p.y + SPARKLINE[1] / 2),
+               Vector2::new(SPARKLINE[0], SPARKLINE[1]), &recent,
+               Vector4::new(1.0, 1.0, 1.0, 0.8));
+
     draw_texture_rect_extra(gd, &gd.bar_base, p, -1.0 * gd.tick as f32,
                             Vector2::new(1.0, 1.0), // trim
                             Vector2::new(1.0, 1.0), // rtrim
@@ -537,22 +597,22 @@ fn draw_map(gd: &GameData) {
 
 fn draw_focus_box(gd: &GameData) {
     let unlock = [0.25, 0.4, 0.6, 0.7, 1.0];
-    draw_texture_rect_screenspace(gd, &gd.focus_box, Point2::new(265, 40));
-    draw_texture_rect_extra(gd, &gd.focus_labels, Point2::new(265, 40), gd.tick as f32,
-                            Vector2::new(1.0, unlock[focus_stage(gd) as usize]),
+    let p = Point2::new(FOCUS_BOX[0], FOCUS_BOX[1]);
+    draw_texture_rect_screenspace(gd, &gd.focus_box, p);
+    draw_texture_rect_extra(gd, &gd.focus_labels, p, gd.tick as f32,
+                            Vector2::new(1.0, unlock[focus_stage(&gd.sim) as usize]),
                             Vector2::new(1.0, 1.0), // rtrim
                             zero(),
                             Vector4::new(1.0, 1.0, 1.0, 1.0));
 
-    draw_texture_rect_screenspace(gd, &gd.arrow, Point2::new(gd.arrow_position.x as i32,
-                                                             gd.arrow_position.y as i32));
+    let arrow = lerp(gd.prev_arrow_position, gd.arrow_position, gd.sim.alpha());
+    draw_texture_rect_screenspace(gd, &gd.arrow, Point2::new(arrow.x as i32,
+                                                             arrow.y as i32));
 }
 
 fn draw_age(gd: &GameData) {
     draw_texture_rect_screenspace(gd, &gd.age_label, Point2::new(175, 17));
-    if gd.age >= 100 { draw_digit(&gd, Point2::new(180, 16), gd.age / 100 % 10); }
-    if gd.age >= 10 { draw_digit(&gd, Point2::new(185, 16), (gd.age / 10) % 10); }
-    draw_digit(&gd, Point2::new(190, 16), gd.age % 10);
+    draw_string(gd, Point2::new(193, 16), &gd.sim.age.to_string(), Align::Right);
 }
 
 fn draw_modal(gd: &GameData, m: &Modal) {
@@ -564,24 +624,193 @@ fn draw_modal(gd: &GameData, m: &Modal) {
                             Vector2::new(1.0, 0.0),
                             Vector4::new(1.0, 1.0, 1.0, 1.0));
 
-    draw_string(gd, Point2::new(WIDTH as i32 / 2, HEIGHT as i32 / 2 - 20),
-    m.text.to_string());
+    // two lines of text fit above the choices; more push the text up
+    let lines = gd.glyphs.wrap(&gd.strings.event(m), MODAL_TEXT_WIDTH);
+    let extra = (lines.len() as i32 - 2).max(0) * gd.glyphs.line_height;
+    draw_lines(gd, Point2::new(WIDTH as i32 / 2, HEIGHT as i32 / 2 - 20 - extra),
+               &lines, Align::Center);
     for (i, c) in m.choices.iter().enumerate() {
         let y = HEIGHT as i32 / 2 + 10 * i as i32;
-        draw_string(gd, Point2::new(WIDTH as i32 / 2,
-                                    y),
-        c.to_string());
+        let text = gd.strings.choice(&gd.sim, m, c);
+        draw_string(gd, Point2::new(WIDTH as i32 / 2, y), &text, Align::Center);
 
         if m.selection == i as i32 {
-            draw_texture_rect_screenspace(
-                gd, &gd.arrow, Point2::new(WIDTH as i32 / 2 - c.len() as i32 * 5, 
-                                           y));
+            let left = WIDTH as i32 / 2 - gd.glyphs.width(&text) / 2;
+            draw_texture_rect_screenspace(gd, &gd.arrow, Point2::new(left - 6, y));
         }
     }
 }
 
+static LOG_LINES: usize = 7; // shown at once on the game over screen
+
+// The life that just ended: an epitaph, where and who with, how it ended up,
+// and the choices made, scrolled through with up and down.
+fn draw_summary(gd: &mut GameData) {
+    let summary = summary::summarize(&gd.sim);
+    let center = (WIDTH / 2) as i32;
+    draw_string(gd, Point2::new(center, 14),
+                &gd.strings.get(&format!("epitaph.{}", summary.epitaph)), Align::Center);
+
+    let homes: Vec<String> = summary.homes.iter().map(|h| gd.strings.city(h)).collect();
+    let married = gd.strings.get(if summary.married { "summary.married" } else { "summary.single" });
+    let kids = match summary.kids {
+        0 => gd.strings.get("summary.no_kids"),
+        1 => gd.strings.get("summary.one_kid"),
+        n => gd.strings.format("summary.kids", &[&n.to_string()]),
+    };
+    let stats = &gd.sim.stats;
+    let about = [
+        gd.strings.format("summary.age", &[&summary.age.to_string()]),
+        gd.strings.format("summary.homes", &[&homes.join(", ")]),
+        gd.strings.format("summary.family", &[&married, &kids]),
+        gd.strings.format("summary.stats", &[&strings::percent(stats.belonging.min(1.0)),
+                                             &strings::percent(stats.purpose.min(1.0)),
+                                             &strings::percent(stats.pride.min(1.0)),
+                                             &strings::percent(stats.relaxation.min(1.0))]),
+    ];
+    let mut lines = vec![];
+    for line in about.iter() {
+        lines.extend(gd.glyphs.wrap(line, WIDTH as i32 - 40));
+    }
+    draw_lines(gd, Point2::new(center, 32), &lines, Align::Center);
+
+    // how each stat went over the whole life
+    let charts = [Stat::Belonging, Stat::Purpose, Stat::Pride, Stat::Relaxation];
+    let spacing = CHART[0] + 8;
+    let left = center - spacing * charts.len() as i32 / 2 + 4;
+    let top = 36 + gd.glyphs.line_height * lines.len() as i32;
+    for (i, &stat) in charts.iter().enumerate() {
+        let x = left + spacing * i as i32;
+        draw_string(gd, Point2::new(x + CHART[0] / 2, top),
+                    &gd.strings.get(&format!("stat.{}", String::from(stat))), Align::Center);
+        let bottom = top + 8 + CHART[1];
+        draw_rect(gd, Point2::new(x, bottom + 1), Point2::new(x + CHART[0] - 1, bottom + 1),
+                  Vector4::new(1.0, 1.0, 1.0, 0.3));
+        let values: Vec<f32> = gd.sim.history.samples.iter().map(|s| s.get(stat)).collect();
+        draw_chart(gd, Point2::new(x, bottom), Vector2::new(CHART[0], CHART[1]), &values,
+                   Vector4::new(0.3, 0.8, 0.3, 1.0));
+    }
+
+    // the choices, and the moves and visits made from the map, oldest first
+    let mut log: Vec<(u32, String)> = gd.sim.modals_done.iter().map(|m| {
+        let choice = &m.choices[m.selection as usize];
+        (m.age, gd.strings.format("summary.choice", &[&m.age.to_string(),
+                                                      &gd.strings.event(m).replace('\n', " "),
+                                                      &gd.strings.choice(&gd.sim, m, choice)]))
+    }).collect();
+    log.extend(gd.sim.journeys.iter().map(|j| {
+        let key = match j.action {
+            Action::Move => "summary.moved",
+            Action::Travel => "summary.visited",
+        };
+        let city = gd.strings.city(&gd.sim.cities[j.city].name);
+        (j.age, gd.strings.format(key, &[&j.age.to_string(), &city]))
+    }));
+    log.sort_by_key(|&(age, _)| age);
+    let log: Vec<String> = log.into_iter().map(|(_, line)| line).collect();
+    gd.log_scroll = gd.log_scroll.min(log.len().saturating_sub(LOG_LINES));
+    let shown: Vec<String> = log.iter().skip(gd.log_scroll).take(LOG_LINES).cloned().collect();
+    draw_lines(gd, Point2::new(20, top + 16 + CHART[1]), &shown, Align::Left);
+
+    draw_string(gd, Point2::new(center, HEIGHT as i32 - 20),
+                &gd.strings.format("summary.keys", &[&binding_name(gd, Input::Confirm),
+                                                     &binding_name(gd, Input::Cancel),
+                                                     &binding_name(gd, Input::Up),
+                                                     &binding_name(gd, Input::Down)]),
+                Align::Center);
+}
+
+// A page of how to play; the keys each page names are filled in.
+fn draw_instructions(gd: &GameData) {
+    let page = gd.sim.page.min(INSTRUCTION_PAGES - 1);
+    let inputs: &[Input] = match page {
+        1 => &[Input::Focus(Focus::Play), Input::Focus(Focus::Socialize),
+               Input::Focus(Focus::Research), Input::Focus(Focus::Create),
+               Input::Focus(Focus::Work)],
+        3 => &[Input::Up, Input::Down, Input::Confirm,
+               Input::Plan(Action::Move), Input::Plan(Action::Travel)],
+        _ => &[],
+    };
+    let keys: Vec<String> = inputs.iter().map(|&i| binding_name(gd, i)).collect();
+    let keys: Vec<&str> = keys.iter().map(|k| k.as_str()).collect();
+    let text = gd.strings.format(&format!("instructions.page{}", page + 1), &keys);
+    draw_string_wrapped(gd, Point2::new((WIDTH / 2) as i32, 40), &text,
+                        Align::Center, WIDTH as i32 - 40);
+    draw_string(gd, Point2::new((WIDTH / 2) as i32, HEIGHT as i32 - 40),
+                &gd.strings.format("instructions.progress", &[&(page + 1).to_string(),
+                                                          &INSTRUCTION_PAGES.to_string()]),
+                Align::Center);
+    draw_string(gd, Point2::new((WIDTH / 2) as i32, HEIGHT as i32 - 30),
+                &gd.strings.format("instructions.keys", &[&binding_name(gd, Input::Confirm),
+                                                          &binding_name(gd, Input::Cancel)]),
+                Align::Center);
+}
+
+// The city picked for a move or trip, with what going there takes; greyed
+// out if there isn't the money for it.
+fn draw_plan(gd: &GameData, plan: &Plan) {
+    let city = &gd.sim.cities[plan.city];
+    let affordable = trips::affordable(&gd.sim, plan.action);
+    let shade = if affordable { 1.0 } else { 0.4 };
+    draw_texture_rect_extra(gd, &gd.arrow, Point2::new(city.position[0] - 8, city.position[1]),
+                            gd.tick as f32,
+                            Vector2::new(1.0, 1.0), // trim
+                            Vector2::new(1.0, 1.0), // rtrim
+                            Vector2::new(0.0, 0.0), // wiggle
+                            Vector4::new(shade, shade, shade, 1.0));
+    let key = match plan.action {
+        Action::Move => "ui.plan_move",
+        Action::Travel => "ui.plan_travel",
+    };
+    let wait = trips::wait_weeks(&gd.sim);
+    let cost = money::text(trips::cost(&gd.sim, plan.action));
+    let detail = if wait > 0 {
+        gd.strings.format("ui.plan_wait", &[&wait.to_string()])
+    } else if !affordable {
+        gd.strings.format("ui.plan_broke", &[&cost])
+    } else {
+        gd.strings.format("ui.plan_cost", &[&cost])
+    };
+    let lines = [
+        gd.strings.format(key, &[&gd.strings.city(&city.name)]),
+        detail,
+        gd.strings.format("ui.plan_keys", &[&binding_name(gd, Input::Confirm),
+                                            &binding_name(gd, Input::Cancel)]),
+    ];
+    draw_lines(gd, Point2::new((WIDTH / 2) as i32, 20), &lines, Align::Center);
+}
+
+// A row of faces above the city, fainter the weaker the bond.
+fn draw_friends(gd: &GameData, city: &City) {
+    let n = city.friends.len() as i32;
+    for (i, f) in city.friends.iter().enumerate() {
+        let x = city.position[0] + (2 * i as i32 - (n - 1)) * 3;
+        draw_texture_rect_extra(gd, &gd.friend, Point2::new(x, city.position[1] - 7),
+                                gd.tick as f32,
+                                Vector2::new(1.0, 1.0), // trim
+                                Vector2::new(1.0, 1.0), // rtrim
+                                Vector2::new(0.0, 0.0), // wiggle
+                                Vector4::new(1.0, 1.0, 1.0, 0.3 + 0.7 * f.bond));
+    }
+}
+
+// Kids still at home, in a row under it, clearer the closer they are.
+fn draw_kids(gd: &GameData, city: &City) {
+    let kids: Vec<&Kid> = gd.sim.kids.iter().filter(|k| k.home).collect();
+    let n = kids.len() as i32;
+    for (i, k) in kids.iter().enumerate() {
+        let x = city.position[0] + (2 * i as i32 - (n - 1)) * 3;
+        draw_texture_rect_extra(gd, &gd.friend, Point2::new(x, city.position[1] + 7),
+                                gd.tick as f32,
+                                Vector2::new(1.0, 1.0), // trim
+                                Vector2::new(1.0, 1.0), // rtrim
+                                Vector2::new(0.0, 0.0), // wiggle
+                                Vector4::new(1.0, 0.8, 0.6, 0.3 + 0.7 * k.bond));
+    }
+}
+
 fn draw_cities(gd: &GameData) {
-    for city in CITIES.lock().unwrap().iter() {
+    for city in gd.sim.cities.iter() {
         let p = Point2::new(city.position[0], city.position[1]);
         if city.home {
             let offset_y = (gd.tick * 4.0).sin().abs() * 3.0;
@@ -595,23 +824,43 @@ fn draw_cities(gd: &GameData) {
         } else {
             draw_texture_rect_screenspace(gd, &gd.city_marker, p);
         }
+        draw_friends(gd, city);
+        if city.home {
+            draw_kids(gd, city);
+        }
+    }
+
+    if let Some(i) = city_at(&gd.sim, gd.cursor_position) {
+        let city = &gd.sim.cities[i];
+        draw_string(gd, Point2::new(city.position[0], city.position[1] - 10),
+                    &gd.strings.city(&city.name), Align::Center);
     }
 }
 
+fn draw_cursor(gd: &GameData) {
+    // the tip of the cursor is its top left corner
+    let half = Vector2::new(gd.cursor.width as i32 / 2, gd.cursor.height as i32 / 2);
+    draw_texture_rect_screenspace(gd, &gd.cursor, gd.cursor_position + half);
+}
+
 fn draw_standard(gd: &mut GameData) {
     draw_water(gd);
     draw_map(gd);
     draw_bar(gd, Point2::new((WIDTH - 50) as i32, (HEIGHT - 20) as i32),
-             &gd.relaxation_label, gd.stats.relaxation);
+             &gd.relaxation_label, Stat::Relaxation);
     draw_bar(gd, Point2::new((WIDTH - 50) as i32, (HEIGHT - 40) as i32), 
-             &gd.belonging_label, gd.stats.belonging);
-    if gd.age > 4 {
+             &gd.belonging_label, Stat::Belonging);
+    if gd.sim.age > 4 {
         draw_bar(gd, Point2::new((WIDTH - 50) as i32, (HEIGHT - 60) as i32),
-                 &gd.pride_label, gd.stats.pride);
+                 &gd.pride_label, Stat::Pride);
     }
-    if gd.age > 13 {
+    if gd.sim.age > 13 {
         draw_bar(gd, Point2::new((WIDTH - 50) as i32, (HEIGHT - 80) as i32), 
-                 &gd.purpose_label, gd.stats.purpose);
+                 &gd.purpose_label, Stat::Purpose);
+    }
+    if gd.sim.age > 15 {
+        draw_string(gd, Point2::new(WIDTH as i32 - 8, (HEIGHT - 97) as i32),
+                    &money::text(gd.sim.stats.money), Align::Right);
     }
     draw_focus_box(&gd);
     draw_age(&gd);
@@ -619,11 +868,54 @@ fn draw_standard(gd: &mut GameData) {
     draw_cities(&gd);
 }
 
+// What to press for an input, for showing on screen.
+fn binding_name(gd: &GameData, input: Input) -> String {
+    let name = match (gd.bindings.keys_for(input).first(),
+                      gd.bindings.buttons_for(input).first()) {
+        (Some(k), _) => k.name(),
+        (None, Some(b)) => b.string(),
+        (None, None) => "?".to_string(),
+    };
+    name.to_lowercase()
+}
+
+fn input_label(gd: &GameData, input: Input) -> String {
+    gd.strings.get(&format!("input.{}", input.name()))
+}
+
+fn draw_rebinding(gd: &GameData, r: &Rebinding) {
+    for (i, &input) in input::INPUTS.iter().enumerate() {
+        let y = 12 + 10 * i as i32;
+        draw_string(gd, Point2::new(90, y), &input_label(gd, input), Align::Center);
+        let bound = if r.waiting && r.selection == i {
+            "?".to_string()
+        } else {
+            binding_name(gd, input)
+        };
+        draw_string(gd, Point2::new(230, y), &bound, Align::Center);
+        if r.selection == i {
+            draw_texture_rect_screenspace(gd, &gd.arrow, Point2::new(20, y));
+        }
+    }
+    let message = match &r.conflict {
+        _ if r.waiting => gd.strings.get("ui.press_to_bind"),
+        Some((name, other)) => {
+            gd.strings.format("ui.already_bound", &[name, &input_label(gd, *other)])
+        }
+        None => String::new(),
+    };
+    draw_string_wrapped(gd, Point2::new((WIDTH / 2) as i32, HEIGHT as i32 - 20), &message,
+                        Align::Center, WIDTH as i32 - 20);
+}
+
 fn draw(ctx: &mut Context) {
     let gd = unsafe { GAME_DATA.as_mut().unwrap() };
 
     ctx.window().clear();
-    match gd.game_state {
+    match gd.sim.game_state {
+        GameState::Title if gd.rebinding.is_some() => {
+            draw_rebinding(gd, gd.rebinding.as_ref().unwrap());
+        }
         GameState::Title => {
             let c = (((gd.tick * 3.0).cos() / 2.0 + 0.5) as f32).max(0.3);
             draw_texture_rect_extra(gd, &gd.title,
@@ -633,27 +925,44 @@ fn draw(ctx: &mut Context) {
                                     Vector2::new(1.0, 1.0), // rtrim
                                     Vector2::new(10.0, 0.0), // wiggle
                                     Vector4::new(0.4, c, 0.4, 1.0));
+            draw_string(gd, Point2::new((WIDTH / 2) as i32, HEIGHT as i32 - 40),
+                        &gd.strings.format("ui.press", &[&binding_name(gd, Input::Start)]),
+                        Align::Center);
+            if gd.has_save {
+                draw_string(gd, Point2::new((WIDTH / 2) as i32, HEIGHT as i32 - 30),
+                            &gd.strings.format(
+                                "ui.continue", &[&binding_name(gd, Input::Continue)]),
+                            Align::Center);
+            }
+            draw_string(gd, Point2::new((WIDTH / 2) as i32, HEIGHT as i32 - 20),
+                        &gd.strings.format("ui.change_keys", &[&binding_name(gd, Input::Rebind)]),
+                        Align::Center);
+            draw_string(gd, Point2::new(8, HEIGHT as i32 - 12),
+                        &gd.strings.format("ui.seed", &[&gd.sim.seed.to_string()]), Align::Left);
+            draw_string(gd, Point2::new(WIDTH as i32 - 8, HEIGHT as i32 - 12),
+                        &gd.strings.format("ui.language", &[&binding_name(gd, Input::Language),
+                                                            &gd.strings.get("language")]),
+                        Align::Right);
         }
         GameState::Modal => {
             draw_standard(gd);
-            if let Some(m) = &gd.current_modal {
+            if let Some(m) = &gd.sim.current_modal {
                 draw_modal(gd, m);
             }
         }
         GameState::Fly => {
             draw_standard(gd);
 
-            let home = home_city();
-            let home_pos = Vector2::new(home.position[0] as f32,
-                                        home.position[1] as f32);
+            let target = fly_target(&gd.sim);
             let rot_matrix = Matrix2::new(gd.plane.width as f32 / WIDTH as f32, 0.0, 
                                           0.0, gd.plane.height as f32 / HEIGHT as f32);
-            let mut delta = home_pos - gd.plane_position;
+            let plane = lerp(gd.prev_plane_position, gd.sim.plane_position, gd.sim.alpha());
+            let mut delta = target - plane;
             delta = delta.normalize();
 
             draw_texture_rect_with_mat2(gd, &gd.plane, 
-                                    Point2::new(gd.plane_position.x as i32,
-                                                gd.plane_position.y as i32), 
+                                    Point2::new(plane.x as i32,
+                                                plane.y as i32), 
                                     gd.tick as f32,
                                     Vector2::new(1.0, 1.0), // trim
                                     Vector2::new(1.0, 1.0), // rtrim
@@ -661,33 +970,65 @@ fn draw(ctx: &mut Context) {
                                     Vector4::new(1.0, 1.0, 1.0, 1.0),
                                     rot_matrix);
         }
+        GameState::Instruction => {
+            draw_instructions(gd);
+        }
+        GameState::Plan => {
+            draw_standard(gd);
+            if let Some(plan) = &gd.sim.plan {
+                draw_plan(gd, plan);
+            }
+        }
         GameState::GameOver => {
             gd.fb.bind();
             unsafe { 
                 gl::ClearColor(0.1, 0.0, 0.1, 1.0);
                 gl::Clear(gl::COLOR_BUFFER_BIT); 
             }
+            draw_summary(gd);
         }
-        GameState::Game | _ => {
+        GameState::Game => {
             draw_standard(gd);
-
-            /* Mouse cursor not yet implemented.
-            draw_texture_rect_screenspace(gd, &gd.cursor, 
-                                          gd.cursor_position / SCALING as i32);
-            */
         }
     }
+    if gd.paused {
+        draw_string(gd, Point2::new((WIDTH / 2) as i32, HEIGHT as i32 - 12),
+                    &gd.strings.get("ui.paused"), Align::Center);
+    }
+    if let (Some(hint), GameState::Game) = (gd.hints.shown(), &gd.sim.game_state) {
+        let keys: Vec<String> = hint.inputs.iter().map(|&i| binding_name(gd, i)).collect();
+        let keys: Vec<&str> = keys.iter().map(|k| k.as_str()).collect();
+        draw_string(gd, Point2::new((WIDTH / 2) as i32, 8),
+                    &gd.strings.format(&format!("hint.{}", hint.id), &keys), Align::Center);
+    }
+    if let Some(shown) = gd.volume_shown {
+        if shown.elapsed() < Duration::from_secs(VOLUME_SHOWN_SECS) {
+            let volume = gd.audio.volume();
+            draw_string(gd, Point2::new((WIDTH / 2) as i32, 8),
+                        &gd.strings.format("ui.volume", &[&strings::percent(volume.music),
+                                                          &strings::percent(volume.sound)]),
+                        Align::Center);
+        }
+    }
+    if let Some(shown) = gd.exported {
+        if shown.elapsed() < Duration::from_secs(EXPORTED_SHOWN_SECS) {
+            draw_string(gd, Point2::new((WIDTH / 2) as i32, HEIGHT as i32 - 8),
+                        &gd.strings.format("ui.exported", &[export::JSON_PATH, export::CSV_PATH]),
+                        Align::Center);
+        }
+    }
+    draw_cursor(gd);
 
     Framebuffer::unbind();
     unsafe { gl::Viewport(0, 0,
-                          (WIDTH * SCALING) as GLint,
-                          (HEIGHT * SCALING) as GLint) };
+                          gd.window_size.x as GLint,
+                          gd.window_size.y as GLint) };
     gd.program.bind_texture("tex", &gd.color_tex, 0);
     gd.program.set_uniform_mat2("transform", &Matrix2::identity());
     gd.program.set_uniform_vec2("offset", &zero());
     gd.program.set_uniform_vec2("trim", &Vector2::new(1.0, 1.0));
     gd.program.set_uniform_vec2("rtrim", &Vector2::new(1.0, 1.0));
-    match gd.game_state {
+    match gd.sim.game_state {
         GameState::Modal => {
             gd.program.set_uniform_vec2("bounce", &Vector2::new(5.0 / WIDTH as f32, 0.0));
         },
@@ -700,375 +1041,172 @@ fn draw(ctx: &mut Context) {
     ctx.swap_buffers();
 }
 
-fn execute_modal(gd: &mut GameData) {
-    let modal = gd.current_modal.as_ref().unwrap();
-    let choice = modal.choices[modal.selection as usize];
-    match modal.kind {
-        ModalKind::Move => {
-            let current_home = home_city();
-            let not_moving = str_eq(choice, "no") || str_eq(choice, current_home.name);
-            gd.plane_position = Vector2::new(current_home.position[0] as f32,
-                                             current_home.position[1] as f32);
-            if !not_moving {
-                set_home_city(choice);
-                gd.moves += 1;
-
-                match gd.moves {
-                    1 => {
-                        gd.stats.relaxation -= 0.05; 
-                        gd.stats.purpose += 0.1;
-                        gd.stats.belonging += 0.1;
-                        gd.stats.pride += 0.25;
-                    }
-                    2 => {
-                        gd.stats.pride += 0.05;
-                    }
-                    3 => {
-                        gd.stats.relaxation -= 0.05; 
-                        gd.stats.purpose -= 0.1;
-                    }
-                    _ => {
-                        gd.stats.belonging -= 0.1;
-                        gd.stats.purpose -= 0.1;
-                    }
-                }
-            } 
+fn lerp(a: Vector2<f32>, b: Vector2<f32>, t: f32) -> Vector2<f32> {
+    a + (b - a) * t
+}
 
-            gd.game_state = GameState::Fly;
-        }
-        ModalKind::Married => {
-            if str_eq(choice, "yes") {
-                gd.stats.purpose += 0.2;
-                gd.stats.belonging += 0.1;
-                gd.stats.relaxation -= 0.1;
-                gd.married = true;
-            }
-            gd.game_state = GameState::Game;
-        }
-        ModalKind::Kids => {
-            if str_eq(choice, "yes") {
-                gd.stats.pride += 0.3;
-                gd.stats.purpose += 0.1;
-                gd.stats.relaxation -= 0.1;
-            }
-            gd.game_state = GameState::Game;
+fn update(dt: f64) {
+    let gd = unsafe { GAME_DATA.as_mut().unwrap() };
+    let prev_plane_position = &mut gd.prev_plane_position;
+    let prev_arrow_position = &mut gd.prev_arrow_position;
+    let arrow_position = &mut gd.arrow_position;
+    gd.sim.advance(dt, |sim| {
+        *prev_plane_position = sim.plane_position;
+        *prev_arrow_position = *arrow_position;
+
+        // arrow
+        if let GameState::Game = sim.game_state {
+            let delta = focus_row(sim.current_focus) as f32 - arrow_position.y;
+            arrow_position.y = arrow_position.y + delta * STEP as f32 * 8.0;
         }
-        ModalKind::Divorce => {
-            if str_eq(choice, "mend") {
-                gd.stats.pride += 0.3;
-                gd.stats.purpose += 0.1;
-                gd.stats.belonging += 0.1;
-                gd.stats.relaxation -= 0.2;
-            }
-
-            if str_eq(choice, "divorce") {
-                gd.stats.pride -= 0.1;
-                gd.stats.purpose -= 0.1;
-                gd.stats.relaxation += 0.1;
-            }
+    });
+    gd.tick = gd.sim.tick + gd.sim.accumulator;
+}
 
-            if str_eq(choice, "suffer") {
-                gd.stats.pride -= 0.1;
-                gd.stats.purpose += 0.3;
-                gd.stats.relaxation -= 0.1;
-            }
-            gd.game_state = GameState::Game;
-        }
-        ModalKind::Tantrum => {
-            if str_eq(choice, "yes") {
-                gd.stats.relaxation += 0.1;
-                gd.stats.social_exp -= 0.1;
-            } else {
-                gd.stats.relaxation -= 0.1;
-                gd.stats.social_exp += 0.1;
+fn tick(ctx: &mut Context, dt: Duration) {
+    let mut commands = handle_input(ctx);
+    let gd = unsafe { GAME_DATA.as_mut().unwrap() };
+    let mut f64_dt = dt.as_secs() as f64 + dt.subsec_nanos() as f64 / 1_000_000_000.0;
+
+    if gd.paused {
+        // nothing happens, and a replay waits where it is; only frames that
+        // ran are recorded
+        f64_dt = 0.0;
+        commands.clear();
+    } else {
+        // while a replay plays, its frames stand in for the clock and the player
+        if let Some(playback) = gd.playback.as_mut() {
+            match playback.next_frame() {
+                Some(frame) => {
+                    f64_dt = frame.dt;
+                    commands = frame.commands;
+                }
+                None => gd.playback = None,
             }
-            gd.game_state = GameState::Game;
-        }
-        ModalKind::Die => {
-            gd.game_state = GameState::GameOver;
-        }
-        _ => {
-            gd.game_state = GameState::Game;
         }
+        gd.recording.record(f64_dt, &commands);
     }
-    gd.modals_done.push(gd.current_modal.take().unwrap());
-}
 
-fn update(dt: f64) {
-    let mut gd = unsafe { GAME_DATA.as_mut().unwrap() };
-
-    match gd.game_state {
-        GameState::Modal => {
+    let was_over = if let GameState::GameOver = gd.sim.game_state { true } else { false };
+    let plane_position = gd.sim.plane_position;
+    for cmd in commands {
+        gd.sim.command(cmd);
+    }
+    // starting a flight puts the plane somewhere new; don't draw it sliding there
+    if gd.sim.plane_position != plane_position {
+        gd.prev_plane_position = gd.sim.plane_position;
+    }
+    update(f64_dt);
+    if let GameState::GameOver = gd.sim.game_state {
+        // every finished life is kept, until the next one finishes, and
+        // there's no more of it to continue
+        if !was_over && gd.playback.is_none() {
+            export_life(gd);
+            let _ = std::fs::remove_file(save::SAVE_PATH);
+            gd.has_save = false;
         }
-        GameState::Fly => {
-            let home = home_city();
-            let home_pos = Vector2::new(home.position[0] as f32,
-                                        home.position[1] as f32);
-            let mut delta = home_pos - gd.plane_position;
-            if delta.magnitude() < 4.0 {
-                gd.game_state = GameState::Game;
-            }
-            delta = delta.normalize();
-            gd.plane_position = gd.plane_position + delta;
+    } else {
+        gd.log_scroll = 0;
+    }
+    gd.audio.update(&gd.sim, gd.paused);
+    if gd.playback.is_none() && gd.hints.update(&gd.sim) {
+        if let Err(e) = hints::write_seen(&gd.hints.seen, Path::new(hints::HINTS_PATH)) {
+            eprintln!("{}", e);
         }
-        GameState::Game => {
-            let dweek = (dt / TICKS_PER_WEEK) as f32;
-
-            // arrow
-            { 
-                let y_offset = 17 + match gd.current_focus {
-                    Focus::Play => 0,
-                    Focus::Socialize => 11,
-                    Focus::Research => 22,
-                    Focus::Create => 33,
-                    Focus::Work => 45,
-                };
-                let delta = y_offset as f32 - gd.arrow_position.y;
-                gd.arrow_position.y = gd.arrow_position.y + delta * dt as f32 * 8.0;
-            }
-
-            // age
-            {
-                gd.age = (gd.tick / (TICKS_PER_WEEK * 50.0)) as u32;
-            }
-
-            if gd.age == 2 {
-                maybe_start_modal(
-                    gd, Modal::new(ModalKind::Tantrum,
-                        "tantrum?", vec!["yes", "no"]));
-            }
-
-            if gd.age == 18 {
-                maybe_start_modal(
-                    gd, Modal::new(ModalKind::Move,
-                                   "goto university?",
-                                   vec!["toronto", "ottawa", "montreal", "no"]));
-            }
-
-            if gd.age == 24 {
-                let home = home_city();
-                maybe_start_modal(
-                    gd, Modal::new(ModalKind::Move,
-                                   "take job?",
-                                   vec![home.name, "vancouver", "sf", "ny"]));
-            }
-
-            if gd.age == 30 {
-                let home = home_city();
-                maybe_start_modal(
-                    gd, Modal::new(ModalKind::Married,
-                                   "get married?",
-                                   vec!["yes", "no"]));
-            }
-
-            if gd.age == 32 && gd.married {
-                let home = home_city();
-                maybe_start_modal(
-                    gd, Modal::new(ModalKind::Kids,
-                                   "spouse wants kids",
-                                   vec!["yes", "no"]));
-            }
-
-            if gd.age == 35 && !gd.married {
-                let home = home_city();
-                maybe_start_modal(
-                    gd, Modal::new(ModalKind::Move,
-                                   "move somewhere\nexciting?",
-                                   vec!["seattle", "calgary", "boulder", "la"]));
-            }
+    }
+    draw(ctx);
+}
 
-            if gd.age == 40 && gd.married {
-                maybe_start_modal(
-                    gd, Modal::new(ModalKind::Divorce,
-                                   "failing marriage",
-                                   vec!["divorce", "mend", "suffer"]));
+fn main() -> Result<(), String> {
+    let mut ctx: Context = Context::new();
+    ctx.open_window("Belonging".to_string(), WIDTH * SCALING, HEIGHT * SCALING);
+    // cursor.png is drawn in its place
+    sdl::show_cursor(false);
+
+    // usage: ggj2019 [--seed N] [--replay file] [--font file] [--mute] [region.toml]
+    // A region file replaces the built in map and cities, and a .bdf or .psf
+    // font the built in font. --mute plays no music or sounds.
+    let mut region_path = None;
+    let mut font_path = None;
+    let mut seed = None;
+    let mut replay_path = None;
+    let mut mute = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--replay" => {
+                replay_path = Some(args.next().ok_or("--replay needs a file")?);
             }
-
-            if gd.age == 60 {
-                let home = home_city();
-                maybe_start_modal(
-                    gd, Modal::new(ModalKind::Move,
-                                   "time to retire?",
-                                   vec![home.name, "miami", "la"]));
+            "--font" => {
+                font_path = Some(args.next().ok_or("--font needs a file")?);
             }
-
-            if gd.age == 70 && !gd.married {
-                let home = home_city();
-                maybe_start_modal(
-                    gd, Modal::new(ModalKind::Die,
-                                   "die?",
-                                   vec!["if i must"]));
+            "--mute" => mute = true,
+            "--seed" => {
+                seed = Some(args.next().and_then(|s| s.parse::<u32>().ok())
+                            .ok_or("--seed needs a number")?);
             }
+            _ => region_path = Some(arg),
+        }
+    }
+    let seed = seed.unwrap_or_else(|| {
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos() ^ d.as_secs() as u32).unwrap_or(0)
+    });
+
+    let (region, map_png) = match region_path {
+        Some(path) => region::load_file(Path::new(&path))?,
+        None => (region::load(include_str!("../assets/region.toml"))?,
+                 include_bytes!("../assets/map.png").to_vec()),
+    };
+    let region::Region { map, city: cities } = region;
+    let events = events::load(include_str!("../assets/events.toml"), &cities)?;
+    let rules = rules::load(include_str!("../assets/rules.toml"))?;
+
+    let english = strings::parse(LOCALES[0].1)?;
+    strings::check(&english, &events)?;
+    let mut strings = Strings::new(english);
+    // start in the system's language if there's a table for it
+    let lang = std::env::var("LANG").unwrap_or_default();
+    if let Some(&(locale, src)) = LOCALES.iter().skip(1).find(|&&(l, _)| lang.starts_with(l)) {
+        strings.set_locale(locale, strings::parse(src)?);
+    }
 
-            if gd.age == 75 {
-                let home = home_city();
-                maybe_start_modal(
-                    gd, Modal::new(ModalKind::Die,
-                                   "die?",
-                                   vec!["if i must"]));
-            }
+    let mut playback = None;
+    let sim = match replay_path {
+        Some(path) => {
+            let r = replay::read(Path::new(&path))?;
+            let sim = save::from_str(&r.start, events, rules)?;
+            playback = Some(Playback::new(r));
+            sim
+        }
+        None => Simulation::new(cities, events, rules, seed),
+    };
+    let recording = Replay::new(&sim)?;
 
-            // stats
-            {
-                match gd.age {
-                    0...2 => {
-                        gd.stats.relaxation -= 0.005 * dweek;
-                        gd.stats.belonging -= 0.005 * dweek;
-                        match gd.current_focus {
-                            Focus::Play => {
-                                gd.stats.relaxation += 0.015 * dweek;
-                            }
-                            Focus::Socialize | _ => {
-                                gd.stats.belonging += 0.015 * dweek;
-                            }
-                        }
-                    }
-                    3...5 => {
-                        gd.stats.relaxation -= 0.005 * dweek;
-                        gd.stats.belonging -= 0.005 * dweek;
-                        gd.stats.pride -= 0.002 * dweek;
-                        if gd.stats.belonging > 0.99 {
-                            gd.stats.pride += 0.006 * dweek;
-                        }
-                        match gd.current_focus {
-                            Focus::Play => {
-                                gd.stats.relaxation += 0.015 * dweek;
-                                gd.stats.play_exp += 0.005 * dweek;
-                            }
-                            Focus::Socialize => {
-                                gd.stats.belonging += 0.015 * dweek;
-                                gd.stats.social_exp += 0.005 * dweek;
-                            }
-                            Focus::Research | _ => {
-                                gd.stats.research_exp += 0.005 * dweek;
-                            }
-                        }
-                    }
-                    6...12 => {
-                        gd.stats.relaxation -= 0.005 * dweek;
-                        gd.stats.belonging -= 0.005 * dweek;
-                        gd.stats.pride -= 0.005 * dweek;
-                        gd.stats.purpose -= 0.005 * dweek;
-                        match gd.current_focus {
-                            Focus::Play => {
-                                gd.stats.relaxation += 0.020 * dweek;
-                                gd.stats.pride += gd.stats.play_exp * dweek / 100.0;
-                                gd.stats.play_exp += 0.005 * dweek;
-                            }
-                            Focus::Socialize | _ => {
-                                gd.stats.belonging += 0.020 * dweek;
-                                gd.stats.pride += 0.005 * dweek;
-                                gd.stats.social_exp += 0.005 * dweek;
-                            }
-                            Focus::Research | _ => {
-                                gd.stats.research_exp += 0.005 * dweek;
-                            }
-                        }
-                    }
-                    13...20 => {
-                        gd.stats.relaxation -= 0.005 * dweek;
-                        gd.stats.belonging -= 0.005 * dweek;
-                        gd.stats.pride -= 0.005 * dweek;
-                        gd.stats.purpose -= 0.005 * dweek;
-                        match gd.current_focus {
-                            Focus::Play => {
-                                gd.stats.relaxation += 0.016 * dweek;
-                                gd.stats.play_exp += 0.001 * dweek;
-                            }
-                            Focus::Socialize => {
-                                gd.stats.belonging += 0.016 * dweek;
-                                gd.stats.social_exp += 0.001 * dweek;
-                            }
-                            Focus::Research => {
-                                gd.stats.belonging += 0.001 * dweek;
-                                gd.stats.pride += 0.001 * dweek;
-                                gd.stats.research_exp += 0.001 * dweek;
-                            }
-                            Focus::Create | _ => {
-                                gd.stats.purpose += 0.0010 * dweek;
-                                gd.stats.pride += 0.0008 * dweek;
-                                gd.stats.create_exp += 0.001 * dweek;
-                            }
-                        }
-                    }
-                    21...40 => {
-                        gd.stats.relaxation -= 0.005 * dweek;
-                        gd.stats.belonging -= 0.005 * dweek;
-                        gd.stats.pride -= 0.005 * dweek;
-                        gd.stats.purpose -= 0.005 * dweek;
-                        match gd.current_focus {
-                            Focus::Play => {
-                                gd.stats.relaxation += 0.017 * dweek;
-                                gd.stats.play_exp += 0.007 * dweek;
-                            }
-                            Focus::Socialize => {
-                                gd.stats.belonging += 0.017 * dweek;
-                                gd.stats.social_exp += 0.007 * dweek;
-                            }
-                            Focus::Research => {
-                                gd.stats.research_exp += 0.002 * dweek;
-                            }
-                            Focus::Create => {
-                                gd.stats.pride += gd.stats.create_exp / 100.0 * dweek;
-                                gd.stats.create_exp += 0.001 * dweek;
-                            }
-                            Focus::Work => {
-                                gd.stats.pride += 0.02 * dweek;
-                                gd.stats.purpose += 0.01 * dweek;
-                            }
-                        }
-                    }
-                    _ => {
-                        gd.stats.relaxation -= 0.003 * dweek;
-                        gd.stats.belonging -= 0.003 * dweek;
-                        gd.stats.pride -= 0.003 * dweek;
-                        gd.stats.purpose -= 0.003 * dweek;
-                        match gd.current_focus {
-                            Focus::Play => {
-                                gd.stats.relaxation += 0.017 * dweek;
-                                gd.stats.play_exp += 0.007 * dweek;
-                            }
-                            Focus::Socialize => {
-                                gd.stats.belonging += 0.017 * dweek;
-                                gd.stats.social_exp += 0.007 * dweek;
-                            }
-                            Focus::Research => {
-                                gd.stats.research_exp += 0.002 * dweek;
-                            }
-                            Focus::Create => {
-                                gd.stats.pride += gd.stats.create_exp / 100.0 * dweek;
-                                gd.stats.create_exp += 0.001 * dweek;
-                            }
-                            Focus::Work => {
-                                gd.stats.pride += 0.02 * dweek;
-                                gd.stats.purpose += 0.01 * dweek;
-                            }
-                        }
-                    }
-                }
+    let (glyphs, mut font_img) = match font_path {
+        Some(path) => font::load_file(Path::new(&path))?,
+        None => (font::load(include_str!("../assets/font.toml"))?,
+                 image::load(&mut Cursor::new(include_bytes!("../assets/font.png").as_ref()),
+                             image::ImageFormat::PNG).unwrap()),
+    };
 
-                gd.stats.belonging = nalgebra::clamp(gd.stats.belonging , 0.0, 1.1);
-                gd.stats.purpose = nalgebra::clamp(gd.stats.purpose, 0.0, 1.1);
-                gd.stats.pride = nalgebra::clamp(gd.stats.pride, 0.0, 1.1);
-                gd.stats.relaxation = nalgebra::clamp(gd.stats.relaxation, 0.0, 1.1);
-            }
-        }
-        _ => {}
-    }
-}
+    let default_bindings = include_str!("../assets/bindings.toml");
+    let bindings = input::load_with_file(default_bindings, Path::new(input::BINDINGS_PATH))
+        .or_else(|e| {
+            eprintln!("{}; using the default bindings", e);
+            input::load(default_bindings)
+        })?;
 
-fn tick(ctx: &mut Context, dt: Duration) {
-    let mut gd = unsafe { GAME_DATA.as_mut().unwrap() };
-    let f64_dt = dt.subsec_nanos() as f64 / 1_000_000_000.0;
-    gd.tick += f64_dt;
-    handle_input(ctx);
-    update(f64_dt);
-    draw(ctx);
-}
+    let volume = audio::load_volume(Path::new(audio::VOLUME_PATH)).unwrap_or_else(|e| {
+        eprintln!("{}; using the default volume", e);
+        audio::Volume::default()
+    });
+    let audio = if mute { Audio::null(volume) } else { Audio::open(volume) };
 
-fn main() -> Result<(), String> {
-    let mut ctx: Context = Context::new();
-    ctx.open_window("Belonging".to_string(), WIDTH * SCALING, HEIGHT * SCALING);
+    let seen = hints::load_seen(Path::new(hints::HINTS_PATH)).unwrap_or_else(|e| {
+        eprintln!("{}; showing every hint again", e);
+        hints::Seen::default()
+    });
 
     // Simple shader
     let mut prog = Program::new("Simple".to_string());
@@ -1099,12 +1237,8 @@ fn main() -> Result<(), String> {
     ))
     .unwrap();
 
-    let mut img = image::load(
-        &mut Cursor::new(include_bytes!("../assets/map.png").as_ref()),
-        image::ImageFormat::PNG,
-    )
-    .unwrap();
-    let tex = Texture::new_rgba_from_image(&mut img);
+    let mut map_img = image::load(&mut Cursor::new(&map_png[..]), image::ImageFormat::PNG)
+        .map_err(|e| format!("region: {}: {}", map, e))?;
 
     let mut fb = Framebuffer::new();
     let color_tex = Texture::new_rgba(WIDTH, HEIGHT);
@@ -1125,19 +1259,35 @@ fn main() -> Result<(), String> {
                     &mut Cursor::new(include_bytes!("../assets/water.png").as_ref()),
                     image::ImageFormat::PNG).unwrap()),
             quad: quad,
-            map: Texture::new_rgba_from_image(
-                &mut image::load(
-                    &mut Cursor::new(include_bytes!("../assets/map.png").as_ref()),
-                    image::ImageFormat::PNG).unwrap()),
+            map: Texture::new_rgba_from_image(&mut map_img),
             cursor: Texture::new_rgba_from_image(
                 &mut image::load(
                     &mut Cursor::new(include_bytes!("../assets/cursor.png").as_ref()),
                     image::ImageFormat::PNG).unwrap()),
             cursor_position: Point2::new(0, 0),
+            window_size: Vector2::new((WIDTH * SCALING) as u32, (HEIGHT * SCALING) as u32),
+            controllers: Controllers::new(),
+            bindings: bindings,
+            rebinding: None,
+            log_scroll: 0,
+            paused: false,
+            strings: strings,
+            audio: audio,
+            volume_shown: None,
+            exported: None,
+            hints: Hints::new(seen),
             city_marker: Texture::new_rgba_from_image(
                 &mut image::load(
                     &mut Cursor::new(include_bytes!("../assets/city_marker.png").as_ref()),
                     image::ImageFormat::PNG).unwrap()),
+            friend: Texture::new_rgba_from_image(
+                &mut image::load(
+                    &mut Cursor::new(include_bytes!("../assets/friend.png").as_ref()),
+                    image::ImageFormat::PNG).unwrap()),
+            dot: Texture::new_rgba_from_image(
+                &mut image::load(
+                    &mut Cursor::new(include_bytes!("../assets/dot.png").as_ref()),
+                    image::ImageFormat::PNG).unwrap()),
             home_marker: Texture::new_rgba_from_image(
                 &mut image::load(
                     &mut Cursor::new(include_bytes!("../assets/home_marker.png").as_ref()),
@@ -1174,14 +1324,8 @@ fn main() -> Result<(), String> {
                 &mut image::load(
                     &mut Cursor::new(include_bytes!("../assets/focus_box.png").as_ref()),
                     image::ImageFormat::PNG).unwrap()),
-            numbers: Texture::new_rgba_from_image(
-                &mut image::load(
-                    &mut Cursor::new(include_bytes!("../assets/numbers.png").as_ref()),
-                    image::ImageFormat::PNG).unwrap()),
-            font: Texture::new_rgba_from_image(
-                &mut image::load(
-                    &mut Cursor::new(include_bytes!("../assets/font.png").as_ref()),
-                    image::ImageFormat::PNG).unwrap()),
+            font: Texture::new_rgba_from_image(&mut font_img),
+            glyphs: glyphs,
             age_label: Texture::new_rgba_from_image(
                 &mut image::load(
                     &mut Cursor::new(include_bytes!("../assets/age_label.png").as_ref()),
@@ -1205,21 +1349,15 @@ fn main() -> Result<(), String> {
             fb: fb,
             color_tex: color_tex,
             light_tex: light_tex,
-            tick: 0.0,
 
-            age: 0,
-            stats: Stats::new(),
-            current_focus: Focus::Play,
-            current_city: 0,
-            married: false,
-            kids: 0,
-            moves: 0,
+            tick: 0.0,
             arrow_position: Vector2::new(262.0, 17.0),
-            plane_position: Vector2::new(0.0, 0.0),
-            modals_done: vec![],
-            //game_state: GameState::Game,
-            game_state: GameState::Title,
-            current_modal: None,
+            prev_arrow_position: Vector2::new(262.0, 17.0),
+            prev_plane_position: Vector2::new(0.0, 0.0),
+            has_save: Path::new(save::SAVE_PATH).exists(),
+            recording: recording,
+            playback: playback,
+            sim: sim,
         })
     };
 
@@ -0,0 +1,97 @@
+// Money: working earns it, life's big steps cost it (through event effects
+// and the price of a flight), and being in debt is stressful. 1.0 is
+// $10,000. The rates are in assets/rules.toml; cities can pay more or less
+// through their "income" attribute (1 if missing).
+use crate::simulation::*;
+
+// Apply one frame's worth (dweek weeks) of earning and owing.
+pub fn apply(sim: &mut Simulation, dweek: f32) {
+    let rules = sim.rules.money.clone();
+    if sim.current_focus == Focus::Work {
+        let city = sim.cities[sim.current_city].attribute("income", 1.0);
+        sim.stats.money += rules.income * (1.0 + sim.stats.work_exp) * city * dweek;
+    }
+    if sim.stats.money < 0.0 {
+        sim.stats.relaxation += rules.debt_stress * sim.stats.money * dweek;
+    }
+}
+
+// What a move costs.
+pub fn pay_flight(sim: &mut Simulation) {
+    sim.stats.money -= sim.rules.money.flight;
+}
+
+// For the HUD: "$12,500", "-$3,000".
+pub fn text(money: f32) -> String {
+    let dollars = (money * 10000.0).round() as i64;
+    let digits = dollars.abs().to_string();
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    format!("{}${}", if dollars < 0 { "-" } else { "" }, grouped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::tests::{city, sim};
+
+    #[test]
+    fn work_pays_more_with_experience_and_in_richer_cities() {
+        let mut sim = sim(1);
+        sim.current_focus = Focus::Work;
+        sim.stats.money = 0.0;
+        sim.stats.work_exp = 0.0;
+        let income = sim.rules.money.income;
+        let city_income = sim.cities[sim.current_city].attribute("income", 1.0);
+        apply(&mut sim, 1.0);
+        assert!((sim.stats.money - income * city_income).abs() < 1e-6);
+
+        sim.stats.money = 0.0;
+        sim.stats.work_exp = 1.0;
+        apply(&mut sim, 1.0);
+        assert!((sim.stats.money - 2.0 * income * city_income).abs() < 1e-6);
+
+        // SF pays 1.5
+        let sf = city(&sim, "SF");
+        set_home_city(&mut sim, "SF");
+        assert_eq!(sim.current_city, sf);
+        sim.stats.money = 0.0;
+        sim.stats.work_exp = 0.0;
+        apply(&mut sim, 1.0);
+        assert!((sim.stats.money - income * 1.5).abs() < 1e-6);
+
+        sim.current_focus = Focus::Play;
+        apply(&mut sim, 1.0);
+        assert!((sim.stats.money - income * 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn debt_is_stressful_by_how_much_is_owed() {
+        let mut sim = sim(1);
+        sim.current_focus = Focus::Play;
+        sim.stats.relaxation = 0.5;
+        sim.stats.money = 0.0;
+        apply(&mut sim, 1.0);
+        assert_eq!(sim.stats.relaxation, 0.5);
+
+        sim.stats.money = -2.0;
+        apply(&mut sim, 1.0);
+        let expected = 0.5 - 2.0 * sim.rules.money.debt_stress;
+        assert!((sim.stats.relaxation - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn money_is_shown_in_dollars() {
+        assert_eq!(text(0.0), "$0");
+        assert_eq!(text(0.05), "$500");
+        assert_eq!(text(1.25), "$12,500");
+        assert_eq!(text(-0.3), "-$3,000");
+        assert_eq!(text(123.4567), "$1,234,567");
+        assert_eq!(text(-100.0), "-$1,000,000");
+    }
+}
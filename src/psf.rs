@@ -0,0 +1,208 @@
+// Loading PC Screen Fonts (PSF1 and PSF2), the Linux console font format.
+// If the font has a Unicode table, glyphs are drawn for the characters it
+// lists; without one, glyph n is drawn for character n.
+use image::DynamicImage;
+
+use crate::font::{self, Font, Glyph};
+
+static PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+static PSF1_MODE512: u8 = 0x01;
+static PSF1_MODEHASTAB: u8 = 0x02;
+static PSF1_MODESEQ: u8 = 0x04;
+static PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+static PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+
+fn u32_at(bytes: &[u8], at: usize) -> Result<u32, String> {
+    if bytes.len() < at + 4 {
+        return Err("psf: file is too short".to_string());
+    }
+    Ok(u32::from(bytes[at]) | u32::from(bytes[at + 1]) << 8 |
+       u32::from(bytes[at + 2]) << 16 | u32::from(bytes[at + 3]) << 24)
+}
+
+struct Header {
+    count: usize,
+    width: usize,
+    height: usize,
+    glyphs_at: usize,
+    table: Option<Table>,
+}
+
+enum Table {
+    Psf1(usize), // where the table starts
+    Psf2(usize),
+}
+
+fn header(bytes: &[u8]) -> Result<Header, String> {
+    if bytes.starts_with(&PSF1_MAGIC) {
+        if bytes.len() < 4 {
+            return Err("psf: file is too short".to_string());
+        }
+        let mode = bytes[2];
+        let count = if mode & PSF1_MODE512 != 0 { 512 } else { 256 };
+        let height = bytes[3] as usize;
+        if height == 0 {
+            return Err("psf: glyphs are 0 pixels high".to_string());
+        }
+        let has_table = mode & (PSF1_MODEHASTAB | PSF1_MODESEQ) != 0;
+        return Ok(Header {
+            count: count,
+            width: 8,
+            height: height,
+            glyphs_at: 4,
+            table: if has_table { Some(Table::Psf1(4 + count * height)) } else { None },
+        });
+    }
+    if bytes.starts_with(&PSF2_MAGIC) {
+        let header_size = u32_at(bytes, 8)? as usize;
+        let flags = u32_at(bytes, 12)?;
+        let count = u32_at(bytes, 16)? as usize;
+        let char_size = u32_at(bytes, 20)? as usize;
+        let height = u32_at(bytes, 24)? as usize;
+        let width = u32_at(bytes, 28)? as usize;
+        if width == 0 || height == 0 {
+            return Err("psf: glyphs are 0 pixels wide or high".to_string());
+        }
+        if char_size != (width + 7) / 8 * height {
+            return Err("psf: glyph size doesn't match its width and height".to_string());
+        }
+        let has_table = flags & PSF2_HAS_UNICODE_TABLE != 0;
+        return Ok(Header {
+            count: count,
+            width: width,
+            height: height,
+            glyphs_at: header_size,
+            table: if has_table {
+                Some(Table::Psf2(header_size + count * char_size))
+            } else {
+                None
+            },
+        });
+    }
+    Err("psf: not a PSF font".to_string())
+}
+
+// The characters each glyph is drawn for. Multi-character sequences, which
+// follow a separator in each glyph's entry, are skipped.
+fn unicode_table(bytes: &[u8], table: &Table, count: usize) -> Result<Vec<Vec<char>>, String> {
+    let mut chars = vec![vec![]; count];
+    match *table {
+        Table::Psf1(mut at) => {
+            for glyph in chars.iter_mut() {
+                let mut in_sequence = false;
+                loop {
+                    if bytes.len() < at + 2 {
+                        return Err("psf: unicode table is cut short".to_string());
+                    }
+                    let u = u32::from(bytes[at]) | u32::from(bytes[at + 1]) << 8;
+                    at += 2;
+                    match u {
+                        0xffff => break,
+                        0xfffe => in_sequence = true,
+                        _ if !in_sequence => glyph.extend(std::char::from_u32(u)),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Table::Psf2(mut at) => {
+            for glyph in chars.iter_mut() {
+                let end = bytes[at.min(bytes.len())..].iter().position(|&b| b == 0xff)
+                    .ok_or("psf: unicode table is cut short")?;
+                let entry = &bytes[at..at + end];
+                at += end + 1;
+                // sequences start at the first 0xfe
+                let singles = match entry.iter().position(|&b| b == 0xfe) {
+                    Some(i) => &entry[..i],
+                    None => entry,
+                };
+                let s = std::str::from_utf8(singles)
+                    .map_err(|_| "psf: unicode table is not UTF-8".to_string())?;
+                glyph.extend(s.chars());
+            }
+        }
+    }
+    Ok(chars)
+}
+
+pub fn load(bytes: &[u8]) -> Result<(Font, DynamicImage), String> {
+    let h = header(bytes)?;
+    let row_bytes = (h.width + 7) / 8;
+    let char_size = row_bytes * h.height;
+    if bytes.len() < h.glyphs_at + h.count * char_size {
+        return Err("psf: file is too short".to_string());
+    }
+
+    let chars = match &h.table {
+        Some(t) => unicode_table(bytes, t, h.count)?,
+        None => (0..h.count as u32).map(|n| std::char::from_u32(n).into_iter().collect())
+            .collect(),
+    };
+
+    let mut glyphs = vec![];
+    for (n, chars) in chars.into_iter().enumerate() {
+        if chars.is_empty() {
+            continue;
+        }
+        let data = &bytes[h.glyphs_at + n * char_size..h.glyphs_at + (n + 1) * char_size];
+        let rows = data.chunks(row_bytes).map(|row| {
+            (0..h.width).map(|x| row[x / 8] & (0x80 >> (x % 8)) != 0).collect()
+        }).collect();
+        glyphs.push(Glyph {
+            chars: chars,
+            advance: h.width as i32,
+            left: 0,
+            top: 0,
+            rows: rows,
+        });
+    }
+    font::atlas(&glyphs, [h.width as u32, h.height as u32], h.height as i32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A PSF2 header for count glyphs of width x height, without the glyphs.
+    fn psf2(count: u32, width: u32, height: u32) -> Vec<u8> {
+        let char_size = (width + 7) / 8 * height;
+        let mut bytes = PSF2_MAGIC.to_vec();
+        for n in [0, 32, 0, count, char_size, height, width].iter() {
+            bytes.extend_from_slice(&n.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn loads_glyphs_for_the_unicode_table() {
+        // two 4x2 glyphs, the second drawn for both B and e acute
+        let mut bytes = psf2(2, 4, 2);
+        bytes[12] = PSF2_HAS_UNICODE_TABLE as u8;
+        bytes.extend_from_slice(&[0x90, 0x60, 0xf0, 0x00]);
+        bytes.extend_from_slice(b"A\xff");
+        bytes.extend_from_slice("B\u{e9}".as_bytes());
+        bytes.push(0xff);
+        let (font, img) = load(&bytes).unwrap();
+        assert_eq!(font.glyph('A'), [0, 0]);
+        assert_eq!(font.glyph('B'), [1, 0]);
+        assert_eq!(font.glyph('\u{e9}'), [1, 0]);
+        assert_eq!(font.advance('A'), 4);
+        let img = img.to_rgba();
+        let on = |x, y| img.get_pixel(x, y)[3] != 0;
+        assert!(on(0, 0) && !on(1, 0) && !on(2, 0) && on(3, 0));
+        assert!(!on(0, 1) && on(1, 1) && on(2, 1) && !on(3, 1));
+        assert!(on(4, 0) && on(7, 0) && !on(4, 1));
+    }
+
+    #[test]
+    fn empty_glyphs_are_an_error() {
+        assert!(load(&psf2(1, 0, 8)).is_err());
+        assert!(load(&psf2(1, 8, 0)).is_err());
+        assert!(load(&[0x36, 0x04, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn short_file_is_an_error() {
+        assert!(load(&psf2(1, 8, 8)).is_err());
+    }
+}
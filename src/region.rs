@@ -0,0 +1,47 @@
+// A region is the map image and the cities on it, loaded from a file like
+// assets/region.toml. See that file for the format.
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::simulation::*;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Region {
+    pub map: String, // image file, relative to the region file
+    pub city: Vec<City>,
+}
+
+pub fn load(src: &str) -> Result<Region, String> {
+    let region: Region = toml::from_str(src)
+        .map_err(|e| format!("region: {}", e))?;
+
+    for (i, c) in region.city.iter().enumerate() {
+        if c.name.is_empty() {
+            return Err(format!("region: city {} has no name", i));
+        }
+        if region.city[..i].iter().any(|other| str_eq(&other.name, &c.name)) {
+            return Err(format!("region: duplicate city '{}'", c.name));
+        }
+    }
+
+    match region.city.iter().filter(|c| c.home).count() {
+        1 => {}
+        0 => return Err("region: no city is marked home = true".to_string()),
+        n => return Err(format!("region: {} cities are marked home = true", n)),
+    }
+
+    Ok(region)
+}
+
+// Load a region and its map image from disk, for regions that aren't built
+// into the game.
+pub fn load_file(path: &Path) -> Result<(Region, Vec<u8>), String> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("region: {}: {}", path.display(), e))?;
+    let region = load(&src)?;
+    let map_path = path.parent().unwrap_or(Path::new(".")).join(&region.map);
+    let map = std::fs::read(&map_path)
+        .map_err(|e| format!("region: {}: {}", map_path.display(), e))?;
+    Ok((region, map))
+}
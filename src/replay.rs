@@ -0,0 +1,137 @@
+// Recording and playing back a session. A replay is the simulation as it was
+// when recording began (in save format) followed by every frame's dt and the
+// commands applied that frame, which is enough to reproduce it exactly.
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::save;
+use crate::simulation::{Command, Simulation};
+
+// Bump whenever a change to Frame or Command makes old replays unreadable.
+pub static REPLAY_VERSION: u32 = 1;
+pub static REPLAY_PATH: &str = "belonging.replay";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Frame {
+    pub dt: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<Command>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub start: String, // see save.rs
+    pub frames: Vec<Frame>,
+}
+
+#[derive(Deserialize)]
+struct ReplayHeader {
+    version: u32,
+}
+
+impl Replay {
+    pub fn new(sim: &Simulation) -> Result<Self, String> {
+        Ok(Self { version: REPLAY_VERSION, start: save::to_string(sim)?, frames: vec![] })
+    }
+
+    pub fn record(&mut self, dt: f64, commands: &[Command]) {
+        self.frames.push(Frame { dt: dt, commands: commands.to_vec() });
+    }
+}
+
+pub struct Playback {
+    replay: Replay,
+    next: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay: replay, next: 0 }
+    }
+
+    pub fn next_frame(&mut self) -> Option<Frame> {
+        let frame = self.replay.frames.get(self.next).cloned();
+        self.next += 1;
+        frame
+    }
+}
+
+pub fn write(replay: &Replay, path: &Path) -> Result<(), String> {
+    let src = serde_json::to_string(replay).map_err(|e| format!("replay: {}", e))?;
+    std::fs::write(path, src).map_err(|e| format!("replay: {}: {}", path.display(), e))
+}
+
+pub fn read(path: &Path) -> Result<Replay, String> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("replay: {}: {}", path.display(), e))?;
+    let header: ReplayHeader = serde_json::from_str(&src)
+        .map_err(|e| format!("replay: {}: {}", path.display(), e))?;
+    if header.version != REPLAY_VERSION {
+        return Err(format!("replay: version {} is not supported (expected {})",
+                           header.version, REPLAY_VERSION));
+    }
+    serde_json::from_str(&src).map_err(|e| format!("replay: {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::tests::sim;
+    use crate::simulation::{Focus, GameState};
+
+    // Uneven frames with the odd command, the way a player would make them.
+    fn session(sim: &mut Simulation, replay: &mut Replay, frames: u32) {
+        let focuses = [Focus::Play, Focus::Socialize, Focus::Research, Focus::Create, Focus::Work];
+        for i in 0..frames {
+            let mut commands = vec![];
+            if let GameState::Modal = sim.game_state {
+                if i % 13 == 0 {
+                    commands.push(Command::Confirm);
+                }
+            } else if i % 97 == 0 {
+                commands.push(Command::SetFocus(focuses[i as usize / 97 % focuses.len()]));
+            }
+            let dt = 0.004 + (i % 7) as f64 * 0.005;
+            replay.record(dt, &commands);
+            for c in commands {
+                sim.command(c);
+            }
+            sim.advance(dt, |_| {});
+        }
+    }
+
+    #[test]
+    fn a_replay_plays_back_the_same_life() {
+        let mut recorded = sim(4);
+        let mut replay = Replay::new(&recorded).unwrap();
+        session(&mut recorded, &mut replay, 20000);
+
+        let path = std::env::temp_dir().join("belonging-test.replay");
+        write(&replay, &path).unwrap();
+        let replay = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut played = save::from_str(&replay.start, recorded.events.clone(),
+                                         recorded.rules.clone()).unwrap();
+        let mut playback = Playback::new(replay);
+        while let Some(frame) = playback.next_frame() {
+            for c in frame.commands {
+                played.command(c);
+            }
+            played.advance(frame.dt, |_| {});
+        }
+        assert_eq!(save::to_string(&played).unwrap(), save::to_string(&recorded).unwrap());
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let mut replay = Replay::new(&sim(1)).unwrap();
+        replay.version = REPLAY_VERSION - 1;
+        let path = std::env::temp_dir().join("belonging-test-old.replay");
+        write(&replay, &path).unwrap();
+        let result = read(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,55 @@
+// A small seeded random number generator (xorshift64*). It is kept in the
+// Simulation and saved with it, so a seed always replays the same life.
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        // splitmix64, so nearby seeds give unrelated streams
+        let mut z = (seed as u64).wrapping_add(0x9e3779b97f4a7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z = z ^ (z >> 31);
+        Self { state: if z == 0 { 0x9e3779b97f4a7c15 } else { z } }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545f4914f6cdd1d) >> 32) as u32
+    }
+
+    // Uniform in [0, 1).
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    #[test]
+    fn a_seed_gives_the_same_numbers() {
+        let (mut a, mut b, mut c) = (Rng::new(7), Rng::new(7), Rng::new(8));
+        let a: Vec<u32> = (0..100).map(|_| a.next_u32()).collect();
+        let b: Vec<u32> = (0..100).map(|_| b.next_u32()).collect();
+        let c: Vec<u32> = (0..100).map(|_| c.next_u32()).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn f32s_are_in_range() {
+        let mut rng = Rng::new(0);
+        for _ in 0..10000 {
+            let f = rng.next_f32();
+            assert!(f >= 0.0 && f < 1.0);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// Putting down roots: the weeks lived in each city (City::exp) make you more
+// rooted there. Being rooted at home adds to belonging; leaving costs more
+// the more rooted you were, and going back to an old home is a homecoming
+// rather than a fresh start. The rates are in assets/rules.toml.
+use crate::simulation::*;
+
+// From 0 (never lived here) towards 1.
+pub fn rootedness(sim: &Simulation, city: &City) -> f32 {
+    let settle = sim.rules.roots.settle;
+    if settle <= 0.0 {
+        return 0.0;
+    }
+    1.0 - (-city.exp / settle).exp()
+}
+
+// Apply one frame's worth (dweek weeks) of living at home.
+pub fn apply(sim: &mut Simulation, dweek: f32) {
+    let i = sim.current_city;
+    sim.cities[i].exp += dweek;
+    let rooted = rootedness(sim, &sim.cities[i]);
+    sim.stats.belonging += sim.rules.roots.belonging * rooted * dweek;
+}
+
+// The cost of leaving the current home for destination, and what returning
+// there gives back.
+pub fn apply_move(sim: &mut Simulation, destination: usize) {
+    let leaving = rootedness(sim, &sim.cities[sim.current_city]);
+    let returning = rootedness(sim, &sim.cities[destination]);
+    sim.stats.belonging -= sim.rules.roots.uproot * leaving;
+    sim.stats.belonging += sim.rules.roots.homecoming * returning;
+}
+
+// How many cities have been lived in.
+pub fn homes(sim: &Simulation) -> u32 {
+    sim.cities.iter().filter(|c| c.exp > 0.0).count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::tests::{city, sim};
+
+    #[test]
+    fn weeks_at_home_settle_you_there() {
+        let mut sim = sim(1);
+        let home = sim.current_city;
+        sim.cities[home].exp = 0.0;
+        assert_eq!(rootedness(&sim, &sim.cities[home]), 0.0);
+
+        let settle = sim.rules.roots.settle;
+        for _ in 0..settle as u32 {
+            apply(&mut sim, 1.0);
+        }
+        assert!((sim.cities[home].exp - settle).abs() < 1e-3);
+        let rooted = rootedness(&sim, &sim.cities[home]);
+        assert!((rooted - (1.0 - (-1.0f32).exp())).abs() < 1e-3);
+
+        // a week now adds to belonging by how rooted you are
+        let before = sim.stats.belonging;
+        apply(&mut sim, 1.0);
+        let rooted = rootedness(&sim, &sim.cities[home]);
+        let gained = sim.stats.belonging - before;
+        assert!((gained - sim.rules.roots.belonging * rooted).abs() < 1e-6);
+        assert_eq!(homes(&sim), 1);
+    }
+
+    #[test]
+    fn leaving_uproots_and_going_back_is_a_homecoming() {
+        let mut sim = sim(1);
+        let (home, away) = (sim.current_city, city(&sim, "Ottawa"));
+        sim.cities[home].exp = sim.rules.roots.settle;
+        sim.cities[away].exp = 0.0;
+        let rooted = rootedness(&sim, &sim.cities[home]);
+
+        let before = sim.stats.belonging;
+        apply_move(&mut sim, away);
+        let lost = before - sim.stats.belonging;
+        assert!((lost - sim.rules.roots.uproot * rooted).abs() < 1e-6);
+
+        // living in the new city, then moving back home
+        sim.current_city = away;
+        sim.cities[away].exp = 1.0;
+        let leaving = rootedness(&sim, &sim.cities[away]);
+        let before = sim.stats.belonging;
+        apply_move(&mut sim, home);
+        let gained = sim.stats.belonging - before;
+        let expected = sim.rules.roots.homecoming * rooted - sim.rules.roots.uproot * leaving;
+        assert!((gained - expected).abs() < 1e-6);
+        assert_eq!(homes(&sim), 2);
+    }
+}
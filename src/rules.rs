@@ -0,0 +1,392 @@
+// Weekly stat rules, loaded from assets/rules.toml. See that file for the
+// format.
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::events::{self, Conditions};
+use crate::simulation::*;
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FocusRule {
+    pub gain: StatChange,
+    pub exp: StatChange, // multiplied by the focus' own exp before adding
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FocusRules {
+    pub play: FocusRule,
+    pub socialize: FocusRule,
+    pub research: FocusRule,
+    pub create: FocusRule,
+    pub work: FocusRule,
+}
+
+impl FocusRules {
+    pub fn get(&self, f: Focus) -> &FocusRule {
+        match f {
+            Focus::Play => &self.play,
+            Focus::Socialize => &self.socialize,
+            Focus::Research => &self.research,
+            Focus::Create => &self.create,
+            Focus::Work => &self.work,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bonus {
+    #[serde(default)]
+    pub require: Conditions,
+    pub gain: StatChange,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Band {
+    pub min_age: u32,
+    pub max_age: Option<u32>, // None for the last band
+    #[serde(default)]
+    pub decay: StatChange,
+    #[serde(default)]
+    pub bonus: Vec<Bonus>,
+    #[serde(default)]
+    pub focus: FocusRules,
+}
+
+// How friends are made and kept; see friends.rs. Rates are per week.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FriendRules {
+    pub meet: f32, // chance of making a friend, while socializing
+    pub max_per_city: usize,
+    pub start_bond: f32,
+    pub grow: f32, // bond gained while socializing, times compatibility
+    pub neglect: f32, // bond lost with friends at home while not socializing
+    pub distance: f32, // bond lost with friends elsewhere, per 100 map pixels away
+    pub belonging: f32, // belonging for each whole bond, less for friends far away
+}
+
+// Earning and owing; see money.rs. Rates are per week.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MoneyRules {
+    pub income: f32, // while working, times (1 + work_exp) and the city's income
+    pub flight: f32, // the cost of every move
+    pub debt_stress: f32, // relaxation lost for each 1.0 of debt
+}
+
+// How being rooted in a city plays out; see roots.rs.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RootRules {
+    pub settle: f32, // weeks in a city to be about two thirds rooted there
+    pub belonging: f32, // a week, for being wholly rooted at home
+    pub uproot: f32, // lost leaving a city you're wholly rooted in
+    pub homecoming: f32, // gained going back to a city you're wholly rooted in
+}
+
+// Raising children; see kids.rs. Rates are per week, for each kid.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KidRules {
+    pub need: f32, // need added while not socializing
+    pub care: f32, // need met while socializing, shared between the kids at home
+    pub bond: f32, // bond gained while socializing, shared the same way
+    pub neglect: f32, // bond lost, times need
+    pub time: f32, // share of socializing each kid at home takes from friends
+    pub purpose: f32, // while the kid is at home
+    pub stress: f32, // relaxation lost, times need
+    pub belonging: f32, // for each whole bond, for the rest of the life
+    pub leave_age: u32, // when kids move out
+}
+
+// Moves and trips the player picks; see trips.rs. A move costs
+// money.flight, like any other.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TripRules {
+    pub min_age: u32, // before this you go where your parents go
+    pub cooldown: f32, // weeks after a move or trip before the next
+    pub travel: f32, // the cost of a trip
+    pub relaxation: f32, // gained on arriving
+    pub visit: f32, // bond gained with each friend in the city visited
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    pub band: Vec<Band>,
+    #[serde(default)]
+    pub clamp: BTreeMap<Stat, [f32; 2]>,
+    #[serde(default)]
+    pub friends: FriendRules,
+    #[serde(default)]
+    pub roots: RootRules,
+    #[serde(default)]
+    pub money: MoneyRules,
+    #[serde(default)]
+    pub kids: KidRules,
+    #[serde(default)]
+    pub trips: TripRules,
+}
+
+impl Rules {
+    pub fn band_index(&self, age: u32) -> usize {
+        for (i, b) in self.band.iter().enumerate() {
+            if age >= b.min_age && b.max_age.map_or(true, |max| age <= max) {
+                return i;
+            }
+        }
+        unreachable!("rules were validated to cover every age");
+    }
+}
+
+pub fn focus_exp(f: Focus) -> Stat {
+    match f {
+        Focus::Play => Stat::PlayExp,
+        Focus::Socialize => Stat::SocialExp,
+        Focus::Research => Stat::ResearchExp,
+        Focus::Create => Stat::CreateExp,
+        Focus::Work => Stat::WorkExp,
+    }
+}
+
+pub fn load(src: &str) -> Result<Rules, String> {
+    let rules: Rules = toml::from_str(src)
+        .map_err(|e| format!("rules: {}", e))?;
+
+    // bands must run from age 0 upwards with no gaps or overlaps, and the
+    // last one must be open ended.
+    let mut next_age = 0;
+    for (i, b) in rules.band.iter().enumerate() {
+        if b.min_age != next_age {
+            return Err(format!("rules: band {} starts at age {}, expected {}",
+                               i, b.min_age, next_age));
+        }
+        match b.max_age {
+            Some(max) if max < b.min_age => {
+                return Err(format!("rules: band {} ends before it starts", i));
+            }
+            Some(max) => next_age = max + 1,
+            None if i + 1 != rules.band.len() => {
+                return Err("rules: only the last band may omit max_age".to_string());
+            }
+            None => {}
+        }
+    }
+    match rules.band.last() {
+        Some(b) if b.max_age.is_none() => {}
+        _ => return Err("rules: the last band must omit max_age".to_string()),
+    }
+
+    for (stat, [min, max]) in rules.clamp.iter() {
+        if min > max {
+            return Err(format!("rules: clamp for {:?} has min > max", stat));
+        }
+    }
+    if rules.friends.meet < 0.0 || rules.friends.meet > 1.0 {
+        return Err("rules: friends.meet must be between 0 and 1".to_string());
+    }
+
+    Ok(rules)
+}
+
+// Apply one frame's worth (dweek weeks) of the current band to the stats.
+pub fn apply(sim: &mut Simulation, dweek: f32) {
+    let b = sim.rules.band_index(sim.age);
+
+    for (stat, rate) in sim.rules.band[b].decay.iter() {
+        *sim.stats.get_mut(*stat) -= rate * dweek;
+    }
+
+    let met: Vec<bool> = sim.rules.band[b].bonus.iter()
+        .map(|bonus| events::conditions_met(sim, &bonus.require))
+        .collect();
+    for (bonus, met) in sim.rules.band[b].bonus.iter().zip(met) {
+        if met {
+            for (stat, rate) in bonus.gain.iter() {
+                *sim.stats.get_mut(*stat) += rate * dweek;
+            }
+        }
+    }
+
+    let focus = sim.rules.band[b].focus.get(sim.current_focus);
+    let exp = sim.stats.get(focus_exp(sim.current_focus));
+    for (stat, factor) in focus.exp.iter() {
+        *sim.stats.get_mut(*stat) += exp * factor * dweek;
+    }
+    for (stat, rate) in focus.gain.iter() {
+        *sim.stats.get_mut(*stat) += rate * dweek;
+    }
+
+    for (stat, [min, max]) in sim.rules.clamp.iter() {
+        let v = sim.stats.get_mut(*stat);
+        *v = nalgebra::clamp(*v, *min, *max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::tests::sim;
+    use crate::simulation::*;
+
+    // The stat block the game had before rules.toml, with the changes made
+    // to the rules since: belonging comes from friends rather than from
+    // socializing or research, and working builds work_exp. The rules take
+    // exp times 0.01 where this divided by 100, which can round differently
+    // in the last bit, so the stats only have to agree to within 1e-6.
+    fn baseline(age: u32, focus: Focus, s: &mut Stats, dweek: f32) {
+        match age {
+            0..=2 => {
+                s.relaxation -= 0.005 * dweek;
+                s.belonging -= 0.005 * dweek;
+                if let Focus::Play = focus {
+                    s.relaxation += 0.015 * dweek;
+                }
+            }
+            3..=5 => {
+                s.relaxation -= 0.005 * dweek;
+                s.belonging -= 0.005 * dweek;
+                s.pride -= 0.002 * dweek;
+                if s.belonging > 0.99 {
+                    s.pride += 0.006 * dweek;
+                }
+                match focus {
+                    Focus::Play => {
+                        s.relaxation += 0.015 * dweek;
+                        s.play_exp += 0.005 * dweek;
+                    }
+                    Focus::Socialize => {
+                        s.social_exp += 0.005 * dweek;
+                    }
+                    _ => {
+                        s.research_exp += 0.005 * dweek;
+                    }
+                }
+            }
+            6..=12 => {
+                s.relaxation -= 0.005 * dweek;
+                s.belonging -= 0.005 * dweek;
+                s.pride -= 0.005 * dweek;
+                s.purpose -= 0.005 * dweek;
+                match focus {
+                    Focus::Play => {
+                        s.relaxation += 0.020 * dweek;
+                        s.pride += s.play_exp * dweek / 100.0;
+                        s.play_exp += 0.005 * dweek;
+                    }
+                    _ => {
+                        s.pride += 0.005 * dweek;
+                        s.social_exp += 0.005 * dweek;
+                    }
+                }
+            }
+            13..=20 => {
+                s.relaxation -= 0.005 * dweek;
+                s.belonging -= 0.005 * dweek;
+                s.pride -= 0.005 * dweek;
+                s.purpose -= 0.005 * dweek;
+                match focus {
+                    Focus::Play => {
+                        s.relaxation += 0.016 * dweek;
+                        s.play_exp += 0.001 * dweek;
+                    }
+                    Focus::Socialize => {
+                        s.social_exp += 0.001 * dweek;
+                    }
+                    Focus::Research => {
+                        s.pride += 0.001 * dweek;
+                        s.research_exp += 0.001 * dweek;
+                    }
+                    _ => {
+                        s.purpose += 0.0010 * dweek;
+                        s.pride += 0.0008 * dweek;
+                        s.create_exp += 0.001 * dweek;
+                    }
+                }
+            }
+            _ => {
+                let decay = if age <= 40 { 0.005 } else { 0.003 };
+                s.relaxation -= decay * dweek;
+                s.belonging -= decay * dweek;
+                s.pride -= decay * dweek;
+                s.purpose -= decay * dweek;
+                match focus {
+                    Focus::Play => {
+                        s.relaxation += 0.017 * dweek;
+                        s.play_exp += 0.007 * dweek;
+                    }
+                    Focus::Socialize => {
+                        s.social_exp += 0.007 * dweek;
+                    }
+                    Focus::Research => {
+                        s.research_exp += 0.002 * dweek;
+                    }
+                    Focus::Create => {
+                        s.pride += s.create_exp / 100.0 * dweek;
+                        s.create_exp += 0.001 * dweek;
+                    }
+                    Focus::Work => {
+                        s.pride += 0.02 * dweek;
+                        s.purpose += 0.01 * dweek;
+                        s.work_exp += 0.005 * dweek;
+                    }
+                }
+            }
+        }
+        s.belonging = nalgebra::clamp(s.belonging, 0.0, 1.1);
+        s.purpose = nalgebra::clamp(s.purpose, 0.0, 1.1);
+        s.pride = nalgebra::clamp(s.pride, 0.0, 1.1);
+        s.relaxation = nalgebra::clamp(s.relaxation, 0.0, 1.1);
+    }
+
+    // Money, belonging, purpose, pride and relaxation at level, every exp at exp.
+    fn stats(level: f32, exp: f32) -> Stats {
+        let mut s = Stats::new();
+        for &stat in STATS.iter() {
+            *s.get_mut(stat) = if stat < Stat::PlayExp { level } else { exp };
+        }
+        s
+    }
+
+    #[test]
+    fn default_rules_match_the_baseline() {
+        let focuses = [Focus::Play, Focus::Socialize, Focus::Research, Focus::Create, Focus::Work];
+        let mut sim = sim(1);
+        for &age in [0, 1, 2, 3, 5, 6, 12, 13, 20, 21, 40, 41, 75].iter() {
+            sim.age = age;
+            let unlocked: Vec<Focus> = focuses.iter().cloned()
+                .filter(|&f| focus_is_unlocked(&sim, f)).collect();
+            for (n, &focus) in unlocked.iter().enumerate() {
+                // high enough for the 3-5 bonus, low enough to be clamped, and between
+                for &(level, exp) in [(1.0, 0.0), (0.5, 3.0), (0.001, 10.0)].iter() {
+                    for &dweek in [0.28, 1.0].iter() {
+                        sim.current_focus = focus;
+                        sim.stats = stats(level, exp);
+                        super::apply(&mut sim, dweek);
+                        let mut expected = stats(level, exp);
+                        baseline(age, focus, &mut expected, dweek);
+                        for &stat in STATS.iter() {
+                            let (got, want) = (sim.stats.get(stat), expected.get(stat));
+                            assert!((got - want).abs() < 1e-6,
+                                    "age {} focus {} {:?} from {}: {} != {}",
+                                    age, n, stat, level, got, want);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bands_must_cover_every_age() {
+        let gap = "[[band]]\nmin_age = 0\nmax_age = 2\n[[band]]\nmin_age = 4\n";
+        assert!(super::load(gap).is_err());
+        let open = "[[band]]\nmin_age = 0\nmax_age = 2\n";
+        assert!(super::load(open).is_err());
+        assert!(super::load("[[band]]\nmin_age = 0\n").is_ok());
+    }
+}
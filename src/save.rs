@@ -0,0 +1,94 @@
+// Saving and resuming a life in progress. A save is the whole Simulation
+// except its content (events, rules), which is re-attached on load, written
+// as JSON with a version number in front.
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::events::Events;
+use crate::rules::Rules;
+use crate::simulation::Simulation;
+
+// Bump whenever a change to Simulation makes old saves unreadable.
+pub static SAVE_VERSION: u32 = 1;
+pub static SAVE_PATH: &str = "belonging.sav";
+
+#[derive(Serialize)]
+struct SaveRef<'a> {
+    version: u32,
+    sim: &'a Simulation,
+}
+
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct SaveFile {
+    sim: Simulation,
+}
+
+pub fn to_string(sim: &Simulation) -> Result<String, String> {
+    serde_json::to_string(&SaveRef { version: SAVE_VERSION, sim: sim })
+        .map_err(|e| format!("save: {}", e))
+}
+
+pub fn from_str(src: &str, events: Events, rules: Rules) -> Result<Simulation, String> {
+    let header: SaveHeader = serde_json::from_str(src)
+        .map_err(|e| format!("save: {}", e))?;
+    if header.version != SAVE_VERSION {
+        return Err(format!("save: version {} is not supported (expected {})",
+                           header.version, SAVE_VERSION));
+    }
+    let file: SaveFile = serde_json::from_str(src)
+        .map_err(|e| format!("save: {}", e))?;
+    let mut sim = file.sim;
+    sim.events = events;
+    sim.rules = rules;
+    Ok(sim)
+}
+
+pub fn write(sim: &Simulation, path: &Path) -> Result<(), String> {
+    std::fs::write(path, to_string(sim)?)
+        .map_err(|e| format!("save: {}: {}", path.display(), e))
+}
+
+pub fn read(path: &Path, events: Events, rules: Rules) -> Result<Simulation, String> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("save: {}: {}", path.display(), e))?;
+    from_str(&src, events, rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::tests::{live, sim};
+    use crate::simulation::STEP;
+
+    #[test]
+    fn a_resumed_life_goes_on_the_same() {
+        let mut sim = sim(3);
+        // saved at a few points in the life, whatever is going on then
+        for &seconds in [1.7, 20.0, 25.3, 50.0].iter() {
+            live(&mut sim, (seconds / STEP) as u32);
+            let saved = to_string(&sim).unwrap();
+            let mut resumed = from_str(&saved, sim.events.clone(), sim.rules.clone()).unwrap();
+            assert_eq!(to_string(&resumed).unwrap(), saved);
+
+            let steps = (30.0 / STEP) as u32;
+            live(&mut sim, steps);
+            live(&mut resumed, steps);
+            assert_eq!(to_string(&resumed).unwrap(), to_string(&sim).unwrap());
+        }
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let saved = to_string(&sim(1)).unwrap();
+        let old = saved.replacen(&format!("\"version\":{}", SAVE_VERSION),
+                                 &format!("\"version\":{}", SAVE_VERSION - 1), 1);
+        assert!(old != saved);
+        let sim = sim(1);
+        assert!(from_str(&old, sim.events.clone(), sim.rules.clone()).is_err());
+    }
+}
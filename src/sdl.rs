@@ -0,0 +1,83 @@
+// The SDL calls the sdl2 crate can't make for us here. Its subsystems and
+// game controllers all hang off the Sdl context, which rockwork keeps to
+// itself, and the mixer's music hook isn't wrapped at all. Everything unsafe
+// about them is kept to this file.
+use sdl2::libc::{c_int, c_void};
+use sdl2::sys;
+use std::ptr;
+
+pub enum Subsystem {
+    Audio,
+    GameController,
+}
+
+// Start an SDL subsystem. SDL counts these, so starting one twice is fine.
+pub fn init(subsystem: Subsystem) -> Result<(), String> {
+    let flag = match subsystem {
+        Subsystem::Audio => sys::SDL_INIT_AUDIO,
+        Subsystem::GameController => sys::SDL_INIT_GAMECONTROLLER,
+    };
+    if unsafe { sys::SDL_InitSubSystem(flag) } != 0 {
+        return Err(sdl2::get_error());
+    }
+    Ok(())
+}
+
+pub fn show_cursor(show: bool) {
+    unsafe { sys::SDL_ShowCursor(show as c_int) };
+}
+
+// An open game controller, closed when dropped.
+pub struct GameController {
+    raw: *mut sys::SDL_GameController,
+}
+
+impl GameController {
+    // index is ControllerDeviceAdded's `which`.
+    pub fn open(index: u32) -> Result<Self, String> {
+        let raw = unsafe { sys::SDL_GameControllerOpen(index as c_int) };
+        if raw.is_null() {
+            return Err(sdl2::get_error());
+        }
+        Ok(Self { raw: raw })
+    }
+
+    // What ControllerDeviceRemoved and the other controller events call it.
+    pub fn instance_id(&self) -> i32 {
+        unsafe { sys::SDL_JoystickInstanceID(sys::SDL_GameControllerGetJoystick(self.raw)) }
+    }
+}
+
+impl Drop for GameController {
+    fn drop(&mut self) {
+        unsafe { sys::SDL_GameControllerClose(self.raw) };
+    }
+}
+
+type Fill = Box<dyn FnMut(&mut [u8]) + Send>;
+
+// The mixer's music, made by a function that is handed each buffer to fill
+// on the audio thread, until the hook is dropped.
+pub struct MusicHook {
+    _fill: Box<Fill>, // where the mixer's pointer points
+}
+
+pub fn hook_music<F: FnMut(&mut [u8]) + Send + 'static>(fill: F) -> MusicHook {
+    let mut fill: Box<Fill> = Box::new(Box::new(fill));
+    let data = &mut *fill as *mut Fill as *mut c_void;
+    unsafe { sys::mixer::Mix_HookMusic(Some(call_fill), data) };
+    MusicHook { _fill: fill }
+}
+
+unsafe extern "C" fn call_fill(data: *mut c_void, stream: *mut u8, len: c_int) {
+    let fill = &mut *(data as *mut Fill);
+    fill(std::slice::from_raw_parts_mut(stream, len as usize));
+}
+
+impl Drop for MusicHook {
+    fn drop(&mut self) {
+        // the mixer holds the audio thread off while the hook changes, so
+        // fill is done with once this returns
+        unsafe { sys::mixer::Mix_HookMusic(None, ptr::null_mut()) };
+    }
+}
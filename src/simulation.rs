@@ -0,0 +1,882 @@
+// The life simulation. Nothing in here may touch SDL or GL; the renderer in
+// main.rs only reads from a Simulation and feeds it Commands.
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use crate::events::{self, Choice, Event, Events};
+use crate::friends;
+use crate::history::{self, History};
+use crate::kids;
+use crate::money;
+use crate::roots;
+use crate::trips;
+use crate::rules::{self, Rules};
+use crate::rng::Rng;
+
+pub static TICKS_PER_WEEK: f64 = 0.06;
+// The simulation only ever advances in steps of this many seconds, so the
+// same input gives the same life at any frame rate.
+pub static STEP: f64 = 1.0 / 60.0;
+// Longer frames (a hitch, a dragged window) are cut short so the game slows
+// down instead of skipping ahead.
+static MAX_FRAME_DT: f64 = 0.25;
+static PLANE_SPEED: f32 = 60.0; // map pixels per second
+// How many pages of how to play are shown before the life starts; the text
+// is in the string tables.
+pub static INSTRUCTION_PAGES: u32 = 4;
+
+#[derive(Serialize, Deserialize)]
+pub enum GameState {
+    Title,
+    Instruction,
+    Game,
+    Modal,
+    Plan, // picking a city to move or travel to
+    Fly,
+    GameOver,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Modal {
+    pub event: String, // id of the Event this modal was started from
+    pub kind: ModalKind,
+    pub choices: Vec<Choice>,
+    pub selection: i32,
+    pub age: u32, // when it came up
+}
+
+impl Modal {
+    pub fn new(event: &Event, choices: Vec<Choice>, age: u32) -> Self {
+        Self { event: event.id.clone(), kind: event.kind,
+               choices: choices, selection: 0, age: age }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModalKind {
+    Notice,
+    Tantrum,
+    Move,
+    Married,
+    Divorce,
+    Kids,
+    Die,
+}
+
+// idea? everything starts negative?
+#[derive(Serialize, Deserialize)]
+pub struct Stats {
+    pub money: f32, // Debt
+    pub belonging: f32, // Loneliness
+    pub purpose: f32, // Ennui
+    pub pride: f32, // Shame
+    pub relaxation: f32,  // Stress
+
+    pub play_exp: f32,
+    pub social_exp: f32,
+    pub research_exp: f32,
+    pub create_exp: f32,
+    pub work_exp: f32,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Self {
+            money: 0.0,
+            belonging: 1.0,
+            purpose: 1.0,
+            pride: 1.0,
+            relaxation: 1.0,
+
+            play_exp: 0.0,
+            social_exp: 0.0,
+            research_exp: 0.0,
+            create_exp: 0.0,
+            work_exp: 0.0,
+        }
+    }
+
+    pub fn get(&self, stat: Stat) -> f32 {
+        match stat {
+            Stat::Money => self.money,
+            Stat::Belonging => self.belonging,
+            Stat::Purpose => self.purpose,
+            Stat::Pride => self.pride,
+            Stat::Relaxation => self.relaxation,
+            Stat::PlayExp => self.play_exp,
+            Stat::SocialExp => self.social_exp,
+            Stat::ResearchExp => self.research_exp,
+            Stat::CreateExp => self.create_exp,
+            Stat::WorkExp => self.work_exp,
+        }
+    }
+
+    pub fn get_mut(&mut self, stat: Stat) -> &mut f32 {
+        match stat {
+            Stat::Money => &mut self.money,
+            Stat::Belonging => &mut self.belonging,
+            Stat::Purpose => &mut self.purpose,
+            Stat::Pride => &mut self.pride,
+            Stat::Relaxation => &mut self.relaxation,
+            Stat::PlayExp => &mut self.play_exp,
+            Stat::SocialExp => &mut self.social_exp,
+            Stat::ResearchExp => &mut self.research_exp,
+            Stat::CreateExp => &mut self.create_exp,
+            Stat::WorkExp => &mut self.work_exp,
+        }
+    }
+
+    pub fn apply(&mut self, change: &StatChange) {
+        for (stat, delta) in change.iter() {
+            *self.get_mut(*stat) += *delta;
+        }
+    }
+}
+
+// Names a single field of Stats, so content files can refer to stats by name
+// ("belonging", "play_exp", ...).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Stat {
+    Money,
+    Belonging,
+    Purpose,
+    Pride,
+    Relaxation,
+
+    PlayExp,
+    SocialExp,
+    ResearchExp,
+    CreateExp,
+    WorkExp,
+}
+
+// Every stat, in the order history samples hold them.
+pub static STATS: [Stat; 10] = [
+    Stat::Money,
+    Stat::Belonging,
+    Stat::Purpose,
+    Stat::Pride,
+    Stat::Relaxation,
+    Stat::PlayExp,
+    Stat::SocialExp,
+    Stat::ResearchExp,
+    Stat::CreateExp,
+    Stat::WorkExp,
+];
+
+// Converted to and from strings by hand rather than derived, so stats can be
+// used as table keys.
+impl TryFrom<String> for Stat {
+    type Error = String;
+    fn try_from(s: String) -> Result<Self, String> {
+        match s.as_str() {
+            "money" => Ok(Stat::Money),
+            "belonging" => Ok(Stat::Belonging),
+            "purpose" => Ok(Stat::Purpose),
+            "pride" => Ok(Stat::Pride),
+            "relaxation" => Ok(Stat::Relaxation),
+            "play_exp" => Ok(Stat::PlayExp),
+            "social_exp" => Ok(Stat::SocialExp),
+            "research_exp" => Ok(Stat::ResearchExp),
+            "create_exp" => Ok(Stat::CreateExp),
+            "work_exp" => Ok(Stat::WorkExp),
+            _ => Err(format!("unknown stat '{}'", s)),
+        }
+    }
+}
+
+impl From<Stat> for String {
+    fn from(stat: Stat) -> String {
+        match stat {
+            Stat::Money => "money",
+            Stat::Belonging => "belonging",
+            Stat::Purpose => "purpose",
+            Stat::Pride => "pride",
+            Stat::Relaxation => "relaxation",
+            Stat::PlayExp => "play_exp",
+            Stat::SocialExp => "social_exp",
+            Stat::ResearchExp => "research_exp",
+            Stat::CreateExp => "create_exp",
+            Stat::WorkExp => "work_exp",
+        }.to_string()
+    }
+}
+
+pub type StatChange = BTreeMap<Stat, f32>;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Focus {
+    Play,
+    Socialize,
+    Research,
+    Create,
+    Work,
+}
+
+pub fn focus_stage(sim: &Simulation) -> u32 {
+    match sim.age {
+        0 => 0,
+        1...7 => 1,
+        8...14 => 2,
+        15...21 => 3,
+        _ => 4,
+    }
+}
+
+pub fn focus_is_unlocked(sim: &Simulation, f: Focus) -> bool {
+    match f {
+        Focus::Play => true,
+        Focus::Socialize => sim.age >= 1,
+        Focus::Research => sim.age >= 8,
+        Focus::Create => sim.age >= 15,
+        Focus::Work => sim.age >= 22
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Move, // live there from now on
+    Travel, // visit, then fly back home
+}
+
+// A move or trip being planned, and the city picked for it so far.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Plan {
+    pub action: Action,
+    pub city: usize, // into cities
+}
+
+// A move or trip made from the map, kept for the game over screen and
+// exports; moves events offer are in modals_done instead.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Journey {
+    pub age: u32,
+    pub action: Action,
+    pub city: usize, // into cities
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Friend {
+    pub bond: f32,
+    pub compatibility: f32,
+    pub exp: f32, // time spent with friend
+    //name: &'static str,
+}
+
+// A child. Kids live at home until they're grown, needing time the parent
+// spends socializing; see kids.rs.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Kid {
+    pub weeks: f32, // age, in weeks
+    pub need: f32, // 0 (looked after) to 1 (badly neglected)
+    pub bond: f32,
+    pub home: bool, // not yet grown and moved out
+    pub milestone: u32, // the last age whose milestones were offered
+}
+
+impl Kid {
+    pub fn age(&self) -> u32 {
+        (self.weeks / 50.0) as u32
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct City {
+    pub name: String,
+    pub position: [i32; 2],
+    #[serde(default)]
+    pub friends: Vec<Friend>,
+    #[serde(default)]
+    pub home: bool,
+    #[serde(default)]
+    pub exp: f32, // time spent here
+    #[serde(default)]
+    pub attributes: BTreeMap<String, f32>,
+}
+
+impl City {
+    pub fn attribute(&self, name: &str, default: f32) -> f32 {
+        *self.attributes.get(name).unwrap_or(&default)
+    }
+}
+
+pub fn str_eq(s1: &str, s2: &str) -> bool {
+    return s1.to_string().to_lowercase() == s2.to_string().to_lowercase();
+}
+
+pub fn home_city(sim: &Simulation) -> City {
+    sim.cities.iter().find(|c| c.home)
+        .expect("regions are validated to have a home city").clone()
+}
+
+// Returns false, leaving the home unchanged, if there is no such city.
+pub fn set_home_city(sim: &mut Simulation, new_home: &str) -> bool {
+    let new_index = match sim.cities.iter().position(|c| str_eq(&c.name, new_home)) {
+        Some(i) => i,
+        None => return false,
+    };
+    for (i, c) in sim.cities.iter_mut().enumerate() {
+        c.home = i == new_index;
+    }
+    sim.current_city = new_index;
+    true
+}
+
+// Input as the simulation understands it. What each command does depends on
+// the current GameState; commands that don't apply are ignored.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Command {
+    Start,
+    Up,
+    Down,
+    Confirm,
+    Select(i32), // a modal choice, or a city while planning, by index
+    Cancel,
+    SetFocus(Focus),
+    Plan(Action),
+    DebugMove,
+}
+
+// Everything but the content (events, rules) is saved; see save.rs.
+#[derive(Serialize, Deserialize)]
+pub struct Simulation {
+    pub tick: f64,
+    // Seconds of the life lived so far. Unlike tick it only runs in
+    // GameState::Game, so age, the weeks and anything timed in them stand
+    // still while a modal is open, a move is planned or the plane flies.
+    pub life: f64,
+    pub accumulator: f64, // real time not yet simulated, < STEP
+    pub seed: u32,
+    pub rng: Rng,
+    pub random_week: u64, // last week random events were rolled for
+    pub page: u32, // of the instructions, while they're shown
+
+    pub age: u32,
+    pub stats: Stats,
+    pub current_focus: Focus,
+    pub current_city: usize, // the home, into cities
+    pub birthplace: usize, // into cities
+    pub cities: Vec<City>,
+    #[serde(skip)]
+    pub events: Events,
+    #[serde(skip)]
+    pub rules: Rules,
+    pub married: bool,
+    pub kids: Vec<Kid>,
+    pub history: History,
+    pub moves: u32,
+    pub plane_position: Vector2<f32>,
+    pub plan: Option<Plan>,
+    pub trip: Option<usize>, // the city being visited, while flying there
+    pub trip_ready: f64, // life from which another move or trip can be made
+    pub journeys: Vec<Journey>, // oldest first
+    pub current_modal: Option<Modal>,
+    pub modals_done: Vec<Modal>,
+    pub game_state: GameState,
+}
+
+impl Simulation {
+    pub fn new(cities: Vec<City>, events: Events, rules: Rules, seed: u32) -> Self {
+        Self {
+            tick: 0.0,
+            life: 0.0,
+            accumulator: 0.0,
+            seed: seed,
+            rng: Rng::new(seed),
+            random_week: 0,
+            page: 0,
+
+            age: 0,
+            stats: Stats::new(),
+            current_focus: Focus::Play,
+            current_city: cities.iter().position(|c| c.home).unwrap_or(0),
+            birthplace: cities.iter().position(|c| c.home).unwrap_or(0),
+            cities: cities,
+            events: events,
+            rules: rules,
+            married: false,
+            kids: vec![],
+            history: History::new(),
+            moves: 0,
+            plane_position: Vector2::new(0.0, 0.0),
+            plan: None,
+            trip: None,
+            trip_ready: 0.0,
+            journeys: vec![],
+            current_modal: None,
+            modals_done: vec![],
+            game_state: GameState::Title,
+        }
+    }
+
+    pub fn command(&mut self, cmd: Command) {
+        match self.game_state {
+            GameState::Title => {
+                if let Command::Start = cmd {
+                    self.game_state = GameState::Instruction;
+                    self.page = 0;
+                }
+            }
+            GameState::Instruction => {
+                match cmd {
+                    Command::Up => {
+                        self.page = self.page.saturating_sub(1);
+                    }
+                    Command::Start | Command::Down | Command::Confirm => {
+                        self.page += 1;
+                        if self.page >= INSTRUCTION_PAGES {
+                            self.begin();
+                        }
+                    }
+                    Command::Cancel => {
+                        self.begin();
+                    }
+                    _ => {}
+                }
+            }
+            GameState::GameOver => {
+                match cmd {
+                    Command::Start | Command::Confirm => {
+                        self.new_life();
+                        self.begin();
+                    }
+                    Command::Cancel => {
+                        self.new_life();
+                    }
+                    _ => {}
+                }
+            }
+            GameState::Modal => {
+                match cmd {
+                    Command::Up => {
+                        let mo = self.current_modal.as_mut().unwrap();
+                        mo.selection -= 1;
+                        if mo.selection < 0 {
+                            mo.selection = mo.choices.len() as i32 - 1;
+                        }
+                    }
+                    Command::Down => {
+                        let mo = self.current_modal.as_mut().unwrap();
+                        mo.selection += 1;
+                        mo.selection %= mo.choices.len() as i32;
+                    }
+                    Command::Select(i) => {
+                        let mo = self.current_modal.as_mut().unwrap();
+                        if i >= 0 && i < mo.choices.len() as i32 {
+                            mo.selection = i;
+                        }
+                    }
+                    Command::Confirm => {
+                        execute_modal(self);
+                    }
+                    _ => {}
+                }
+            }
+            GameState::Plan => {
+                match cmd {
+                    Command::Up => trips::pick(self, -1),
+                    Command::Down => trips::pick(self, 1),
+                    Command::Select(i) => {
+                        let i = i as usize;
+                        if i < self.cities.len() && i != self.current_city {
+                            self.plan.as_mut().unwrap().city = i;
+                        }
+                    }
+                    Command::Plan(action) => {
+                        self.plan.as_mut().unwrap().action = action;
+                    }
+                    Command::Confirm => {
+                        trips::go(self);
+                    }
+                    Command::Cancel => {
+                        self.plan = None;
+                        self.game_state = GameState::Game;
+                    }
+                    _ => {}
+                }
+            }
+            GameState::Game => {
+                match cmd {
+                    Command::Up => {
+                        self.current_focus = match self.current_focus {
+                            Focus::Work => Focus::Create,
+                            Focus::Create => Focus::Research,
+                            Focus::Research => Focus::Socialize,
+                            _ => Focus::Play,
+                        };
+                    }
+                    Command::Down => {
+                        let next = match self.current_focus {
+                            Focus::Play => Focus::Socialize,
+                            Focus::Socialize => Focus::Research,
+                            Focus::Research => Focus::Create,
+                            _ => Focus::Work,
+                        };
+                        if focus_is_unlocked(self, next) {
+                            self.current_focus = next;
+                        }
+                    }
+                    Command::SetFocus(f) => {
+                        if focus_is_unlocked(self, f) {
+                            self.current_focus = f;
+                        }
+                    }
+                    Command::Plan(action) => {
+                        trips::plan(self, action);
+                    }
+                    Command::DebugMove => {
+                        let university = self.events.list.iter()
+                            .find(|ev| ev.id == "university").cloned();
+                        if let Some(ev) = university {
+                            start_modal(self, &ev);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Another life in the same world, at the title. Its seed comes from this
+    // one's, so a replay still plays the same lives.
+    fn new_life(&mut self) {
+        let mut cities = std::mem::take(&mut self.cities);
+        for (i, c) in cities.iter_mut().enumerate() {
+            c.home = i == self.birthplace;
+            c.friends.clear();
+            c.exp = 0.0;
+        }
+        let seed = self.rng.next_u32();
+        let events = std::mem::take(&mut self.events);
+        let rules = std::mem::take(&mut self.rules);
+        *self = Simulation::new(cities, events, rules, seed);
+    }
+
+    // The life starts now.
+    fn begin(&mut self) {
+        self.game_state = GameState::Game;
+        self.tick = 0.0;
+        self.life = 0.0;
+        self.random_week = 0;
+    }
+
+    // Simulate as many whole STEPs as dt (plus what's left from before)
+    // covers, calling before_step ahead of each one.
+    pub fn advance<F: FnMut(&Simulation)>(&mut self, dt: f64, mut before_step: F) {
+        self.accumulator += dt.min(MAX_FRAME_DT);
+        while self.accumulator >= STEP {
+            self.accumulator -= STEP;
+            before_step(self);
+            self.step(STEP);
+        }
+    }
+
+    // How far between the last step and the next the current time is, for
+    // interpolating what's drawn.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / STEP) as f32
+    }
+
+    // Advance the simulation by dt seconds.
+    pub fn step(&mut self, dt: f64) {
+        self.tick += dt;
+        update(self, dt);
+    }
+}
+
+pub fn start_modal(sim: &mut Simulation, ev: &Event) {
+    let choices = events::resolve_choices(sim, ev);
+    sim.current_modal = Some(Modal::new(ev, choices, sim.age));
+    sim.game_state = GameState::Modal;
+}
+
+pub fn maybe_start_modal(sim: &mut Simulation, ev: &Event) -> bool {
+    for m in sim.modals_done.iter() {
+        if m.event == ev.id && !ev.repeat {
+            return false;
+        }
+    }
+    if !events::conditions_met(sim, &ev.require) {
+        return false;
+    }
+    start_modal(sim, ev);
+    return true;
+}
+
+// Start the plane from the home city; see GameState::Fly.
+pub fn take_off(sim: &mut Simulation) {
+    let home = home_city(sim);
+    sim.plane_position = Vector2::new(home.position[0] as f32,
+                                      home.position[1] as f32);
+    sim.game_state = GameState::Fly;
+}
+
+// Where the plane is headed: the city being visited, or else home.
+pub fn fly_target(sim: &Simulation) -> Vector2<f32> {
+    let city = &sim.cities[sim.trip.unwrap_or(sim.current_city)];
+    Vector2::new(city.position[0] as f32, city.position[1] as f32)
+}
+
+// Make cities[i] the home, with what moving there does to the stats.
+pub fn move_home(sim: &mut Simulation, i: usize) {
+    // the more places lived in, the harder somewhere new is;
+    // going back to one of them is a homecoming instead (0)
+    let new_place = sim.cities[i].exp == 0.0;
+    let homes = if new_place { roots::homes(sim).max(1) } else { 0 };
+    roots::apply_move(sim, i);
+    money::pay_flight(sim);
+    let name = sim.cities[i].name.clone();
+    set_home_city(sim, &name);
+    sim.moves += 1;
+
+    match homes {
+        0 => {}
+        1 => {
+            sim.stats.relaxation -= 0.05;
+            sim.stats.purpose += 0.1;
+            sim.stats.belonging += 0.1;
+            sim.stats.pride += 0.25;
+        }
+        2 => {
+            sim.stats.pride += 0.05;
+        }
+        3 => {
+            sim.stats.relaxation -= 0.05;
+            sim.stats.purpose -= 0.1;
+        }
+        _ => {
+            sim.stats.belonging -= 0.1;
+            sim.stats.purpose -= 0.1;
+        }
+    }
+}
+
+fn execute_modal(sim: &mut Simulation) {
+    let modal = sim.current_modal.take().unwrap();
+    let choice = &modal.choices[modal.selection as usize];
+    sim.stats.apply(&choice.effects);
+    if let Some(married) = choice.married {
+        sim.married = married;
+    }
+    if choice.kid {
+        kids::born(sim);
+    }
+    match modal.kind {
+        ModalKind::Move => {
+            let destination = choice.city.as_ref()
+                .and_then(|city| sim.cities.iter().position(|c| str_eq(&c.name, city)))
+                .filter(|&i| i != sim.current_city);
+            take_off(sim);
+            if let Some(i) = destination {
+                move_home(sim, i);
+            }
+        }
+        ModalKind::Die => {
+            sim.game_state = GameState::GameOver;
+        }
+        _ => {
+            sim.game_state = GameState::Game;
+        }
+    }
+    sim.modals_done.push(modal);
+}
+
+fn update(sim: &mut Simulation, dt: f64) {
+    match sim.game_state {
+        GameState::Modal => {
+        }
+        GameState::Fly => {
+            let delta = fly_target(sim) - sim.plane_position;
+            if delta.magnitude() < 4.0 {
+                match sim.trip.take() {
+                    // there, and now back home
+                    Some(city) => trips::arrive(sim, city),
+                    // landed; staying put lands straight away
+                    None => sim.game_state = GameState::Game,
+                }
+                return;
+            }
+            sim.plane_position = sim.plane_position + delta.normalize() * PLANE_SPEED * dt as f32;
+        }
+        GameState::Game => {
+            let dweek = (dt / TICKS_PER_WEEK) as f32;
+            sim.life += dt;
+
+            // age
+            {
+                sim.age = (sim.life / (TICKS_PER_WEEK * 50.0)) as u32;
+            }
+
+            for i in 0..sim.events.list.len() {
+                let ev = &sim.events.list[i];
+                if ev.weight.is_none() && ev.at_age(sim.age) {
+                    let ev = ev.clone();
+                    if maybe_start_modal(sim, &ev) {
+                        break;
+                    }
+                }
+            }
+
+            // random events
+            let week = (sim.life / TICKS_PER_WEEK) as u64;
+            if sim.random_week < week {
+                sim.random_week = week;
+                if let GameState::Game = sim.game_state {
+                    events::roll_random(sim);
+                }
+            }
+
+            // stats
+            friends::apply(sim, dweek);
+            roots::apply(sim, dweek);
+            money::apply(sim, dweek);
+            kids::apply(sim, dweek);
+            rules::apply(sim, dweek);
+            history::record(sim);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::region;
+
+    // A life with the built in content, just begun.
+    pub fn sim(seed: u32) -> Simulation {
+        let cities = region::load(include_str!("../assets/region.toml")).unwrap().city;
+        let events = events::load(include_str!("../assets/events.toml"), &cities).unwrap();
+        let rules = rules::load(include_str!("../assets/rules.toml")).unwrap();
+        let mut sim = Simulation::new(cities, events, rules, seed);
+        sim.begin();
+        sim
+    }
+
+    // Skip ahead to years into the life, without living them.
+    pub fn at_age(sim: &mut Simulation, years: f64) {
+        sim.life = years * TICKS_PER_WEEK * 50.0;
+        sim.random_week = (sim.life / TICKS_PER_WEEK) as u64;
+        sim.history.last_week = sim.random_week;
+        sim.step(STEP);
+    }
+
+    // The index of the city called name.
+    pub fn city(sim: &Simulation, name: &str) -> usize {
+        sim.cities.iter().position(|c| str_eq(&c.name, name)).unwrap()
+    }
+
+    // Play for steps STEPs the way a player might: taking the first choice
+    // of everything asked and changing focus every 20 weeks.
+    pub fn live(sim: &mut Simulation, steps: u32) {
+        let focuses = [Focus::Play, Focus::Socialize, Focus::Research, Focus::Create, Focus::Work];
+        for _ in 0..steps {
+            match sim.game_state {
+                GameState::Modal => sim.command(Command::Confirm),
+                GameState::Game => {
+                    let week = (sim.life / TICKS_PER_WEEK) as usize;
+                    sim.command(Command::SetFocus(focuses[week / 20 % focuses.len()]));
+                }
+                _ => {}
+            }
+            sim.step(STEP);
+        }
+    }
+
+    // Live until age, or the end of the life if that comes first.
+    pub fn live_until(sim: &mut Simulation, age: u32) {
+        while sim.age < age {
+            if let GameState::GameOver = sim.game_state {
+                return;
+            }
+            live(sim, 1);
+        }
+    }
+
+    #[test]
+    fn scripted_events_happen_at_their_age() {
+        let mut sim = sim(1);
+        live_until(&mut sim, 31);
+        let done: Vec<(&str, u32)> = sim.modals_done.iter()
+            .map(|m| (m.event.as_str(), m.age)).collect();
+        let expected = [("tantrum", 2), ("university", 18), ("job", 24), ("marriage", 30)];
+        for &expected in expected.iter() {
+            assert!(done.contains(&expected), "{:?} not in {:?}", expected, done);
+        }
+        assert!(sim.married);
+    }
+
+    #[test]
+    fn a_whole_life_ends_and_stays_clamped() {
+        let mut sim = sim(2);
+        for _ in 0..(400.0 / STEP) as u32 {
+            // choices and moves can push past the clamp until a step of play
+            let playing = matches!(sim.game_state, GameState::Game);
+            live(&mut sim, 1);
+            if !playing || !matches!(sim.game_state, GameState::Game) {
+                continue;
+            }
+            for (&stat, &[min, max]) in sim.rules.clamp.iter() {
+                let v = sim.stats.get(stat);
+                assert!(v >= min && v <= max, "{:?} is {}", stat, v);
+            }
+        }
+        assert!(matches!(sim.game_state, GameState::GameOver));
+        assert_eq!(sim.modals_done.last().unwrap().event, "die");
+    }
+
+    #[test]
+    fn a_seed_always_lives_the_same_life() {
+        let lives: Vec<Vec<(String, u32)>> = [4, 4, 5].iter().map(|&seed| {
+            let mut sim = sim(seed);
+            live_until(&mut sim, 80);
+            sim.modals_done.iter().map(|m| (m.event.clone(), m.age)).collect()
+        }).collect();
+        assert!(lives[0] == lives[1]);
+        assert!(lives[0] != lives[2]);
+    }
+
+    // Two minutes of a life at fps frames a second. Without events nothing
+    // waits on the player, whose answers would come a frame later at a
+    // lower frame rate. The last half step keeps rounding in the
+    // accumulator from deciding whether the final step is taken.
+    fn at_frame_rate(fps: f64) -> Simulation {
+        let mut sim = sim(3);
+        sim.events.list.clear();
+        for _ in 0..(120.0 * fps) as u32 {
+            sim.advance(1.0 / fps, |_| {});
+        }
+        sim.advance(STEP / 2.0, |_| {});
+        sim
+    }
+
+    #[test]
+    fn any_frame_rate_lives_the_same_life() {
+        let lives: Vec<Simulation> = [30.0, 60.0, 144.0].iter()
+            .map(|&fps| at_frame_rate(fps)).collect();
+        for sim in lives.iter() {
+            assert!(sim.age > 20);
+            assert_eq!(sim.age, lives[0].age);
+            assert_eq!((sim.tick / STEP).round(), (lives[0].tick / STEP).round());
+            for &stat in STATS.iter() {
+                assert_eq!(sim.stats.get(stat), lives[0].stats.get(stat), "{:?}", stat);
+            }
+        }
+    }
+
+    #[test]
+    fn a_long_frame_is_cut_short() {
+        let mut sim = sim(1);
+        let mut steps = 0;
+        sim.advance(10.0, |_| steps += 1);
+        assert_eq!(steps, (MAX_FRAME_DT / STEP) as u32);
+        assert!(sim.accumulator < STEP);
+    }
+}
@@ -0,0 +1,149 @@
+// Text shown to the player, looked up by key in string tables, one per
+// locale (assets/strings). See en.toml for the keys. A key missing from the
+// current locale is shown in English, and reported once.
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use crate::events::{Choice, Events};
+use crate::simulation::*;
+
+type Table = HashMap<String, String>;
+
+pub struct Strings {
+    pub locale: String,
+    table: Table,
+    english: Table,
+    reported: RefCell<HashSet<String>>,
+}
+
+fn flatten(prefix: &str, value: toml::Value, table: &mut Table) -> Result<(), String> {
+    match value {
+        toml::Value::String(s) => {
+            table.insert(prefix.to_string(), s);
+        }
+        toml::Value::Table(t) => {
+            for (k, v) in t.into_iter() {
+                let key = if prefix.is_empty() { k } else { format!("{}.{}", prefix, k) };
+                flatten(&key, v, table)?;
+            }
+        }
+        _ => return Err(format!("strings: '{}' is not a string", prefix)),
+    }
+    Ok(())
+}
+
+pub fn parse(src: &str) -> Result<Table, String> {
+    let value: toml::Value = toml::from_str(src).map_err(|e| format!("strings: {}", e))?;
+    let mut table = Table::new();
+    flatten("", value, &mut table)?;
+    Ok(table)
+}
+
+fn choice_key(event: &str, choice: &Choice) -> String {
+    format!("choice.{}.{}", event, choice.id)
+}
+
+// English has to have the text of every event and choice; other locales
+// fall back to it.
+pub fn check(english: &Table, events: &Events) -> Result<(), String> {
+    for ev in events.list.iter() {
+        let key = format!("event.{}", ev.id);
+        if !english.contains_key(&key) {
+            return Err(format!("strings: English has no '{}'", key));
+        }
+        for c in ev.choices.iter().filter(|c| c.city.is_none()) {
+            let key = choice_key(&ev.id, c);
+            if !english.contains_key(&key) {
+                return Err(format!("strings: English has no '{}'", key));
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Strings {
+    pub fn new(english: Table) -> Self {
+        Self {
+            locale: "en".to_string(),
+            table: english.clone(),
+            english: english,
+            reported: RefCell::new(HashSet::new()),
+        }
+    }
+
+    pub fn set_locale(&mut self, locale: &str, table: Table) {
+        self.locale = locale.to_string();
+        self.table = table;
+        self.reported.borrow_mut().clear();
+    }
+
+    pub fn get(&self, key: &str) -> String {
+        if let Some(s) = self.table.get(key) {
+            return s.clone();
+        }
+        if self.reported.borrow_mut().insert(key.to_string()) {
+            eprintln!("strings: {} has no '{}'", self.locale, key);
+        }
+        self.english.get(key).cloned().unwrap_or_else(|| key.to_string())
+    }
+
+    // The string for key with each {} replaced by the next of args.
+    pub fn format(&self, key: &str, args: &[&str]) -> String {
+        let mut s = self.get(key);
+        for arg in args.iter() {
+            s = s.replacen("{}", arg, 1);
+        }
+        s
+    }
+
+    // Cities not in the table keep the name they have in the region file,
+    // without being reported.
+    pub fn city(&self, name: &str) -> String {
+        let key = format!("city.{}", name);
+        self.table.get(&key).or_else(|| self.english.get(&key))
+            .cloned().unwrap_or_else(|| name.to_string())
+    }
+
+    pub fn event(&self, m: &Modal) -> String {
+        self.get(&format!("event.{}", m.event))
+    }
+
+    pub fn choice(&self, sim: &Simulation, m: &Modal, choice: &Choice) -> String {
+        match &choice.city {
+            Some(city) => {
+                // the region's spelling, whatever case the event used
+                let name = sim.cities.iter().find(|c| str_eq(&c.name, city))
+                    .map_or(city.clone(), |c| c.name.clone());
+                self.city(&name)
+            }
+            None => self.get(&choice_key(&m.event, choice)),
+        }
+    }
+}
+
+// For numbers from 0 to 1 shown to the player: "50%".
+pub fn percent(v: f32) -> String {
+    format!("{}%", (v * 100.0).round() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_text_falls_back_to_english_and_is_reported_once() {
+        let mut strings = Strings::new(parse("a = \"A\"\n[ui]\nb = \"B {}\"\n").unwrap());
+        strings.set_locale("fr", parse("a = \"À\"\n").unwrap());
+        assert_eq!(strings.get("a"), "À");
+        // every frame asks again
+        for _ in 0..3 {
+            assert_eq!(strings.format("ui.b", &["1"]), "B 1");
+            assert_eq!(strings.get("c"), "c");
+        }
+        assert_eq!(strings.reported.borrow().len(), 2);
+        // a new locale may be missing other things
+        strings.set_locale("fr", parse("c = \"C\"\n").unwrap());
+        assert_eq!(strings.get("c"), "C");
+        assert!(strings.reported.borrow().is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+// What the game over screen says about a life: where it was lived, who with,
+// the choices made along the way, and an epitaph that sums it up from how
+// the stats ended.
+use crate::simulation::*;
+
+// Every stat at least this high is a full life, every one below EMPTY an
+// empty one.
+static FULL: f32 = 0.7;
+static EMPTY: f32 = 0.3;
+// This many moves or more with belonging below 0.5 is a restless life.
+static RESTLESS_MOVES: u32 = 4;
+
+static EPITAPH_STATS: [Stat; 4] = [Stat::Belonging, Stat::Purpose, Stat::Pride, Stat::Relaxation];
+
+pub struct Summary {
+    pub age: u32,
+    pub homes: Vec<String>, // by name, in the order they were lived in
+    pub married: bool,
+    pub kids: usize,
+    pub epitaph: &'static str, // shown as the string epitaph.<id>
+}
+
+pub fn summarize(sim: &Simulation) -> Summary {
+    Summary {
+        age: sim.age,
+        homes: homes(sim),
+        married: sim.married,
+        kids: sim.kids.len(),
+        epitaph: epitaph(sim),
+    }
+}
+
+// Where the life began, then each move, whether an event's or one made from
+// the map. Going back to an old home lists it again.
+fn homes(sim: &Simulation) -> Vec<String> {
+    let mut moves: Vec<(u32, usize)> = sim.modals_done.iter()
+        .filter(|m| matches!(m.kind, ModalKind::Move))
+        .filter_map(|m| {
+            let city = m.choices[m.selection as usize].city.as_ref()?;
+            let i = sim.cities.iter().position(|c| str_eq(&c.name, city))?;
+            Some((m.age, i))
+        }).collect();
+    moves.extend(sim.journeys.iter().filter(|j| j.action == Action::Move).map(|j| (j.age, j.city)));
+    moves.sort_by_key(|&(age, _)| age);
+    // the history keeps its first week however long the life
+    let born = sim.history.samples.first().map_or(sim.current_city, |s| s.home);
+    let mut homes = vec![born];
+    for (_, i) in moves {
+        if homes.last() != Some(&i) {
+            homes.push(i);
+        }
+    }
+    homes.iter().map(|&i| sim.cities[i].name.clone()).collect()
+}
+
+fn epitaph(sim: &Simulation) -> &'static str {
+    let values: Vec<f32> = EPITAPH_STATS.iter().map(|&s| sim.stats.get(s)).collect();
+    if values.iter().all(|&v| v >= FULL) {
+        return "full";
+    }
+    if values.iter().all(|&v| v < EMPTY) {
+        return "empty";
+    }
+    if sim.moves >= RESTLESS_MOVES && sim.stats.belonging < 0.5 {
+        return "restless";
+    }
+    // otherwise, whatever the life had most of
+    let mut best = 0;
+    for i in 1..values.len() {
+        if values[i] > values[best] {
+            best = i;
+        }
+    }
+    match EPITAPH_STATS[best] {
+        Stat::Belonging => "belonging",
+        Stat::Purpose => "purpose",
+        Stat::Pride => "pride",
+        _ => "relaxation",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::tests::{city, live, sim};
+    use crate::simulation::*;
+
+    #[test]
+    fn homes_are_listed_in_the_order_they_were_lived_in() {
+        let mut sim = sim(1);
+        // university in Montreal, then a job in NY
+        while sim.age < 25 {
+            if let Some(m) = sim.current_modal.as_mut() {
+                match m.event.as_str() {
+                    "university" => m.selection = 2,
+                    "job" => m.selection = 3,
+                    _ => {}
+                }
+            }
+            live(&mut sim, 1);
+        }
+        while !matches!(sim.game_state, GameState::Game) {
+            live(&mut sim, 1);
+        }
+        // back to Montreal from the map, after a visit home that doesn't count
+        sim.stats.money = 1.0;
+        sim.journeys.push(Journey { age: 25, action: Action::Travel, city: city(&sim, "Toronto") });
+        sim.command(Command::Plan(Action::Move));
+        sim.command(Command::Select(city(&sim, "Montreal") as i32));
+        sim.command(Command::Confirm);
+        assert_eq!(super::summarize(&sim).homes, ["Toronto", "Montreal", "NY", "Montreal"]);
+    }
+}
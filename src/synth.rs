@@ -0,0 +1,208 @@
+// The music: a small chiptune synthesizer that plays a four bar loop whose
+// tempo, key and instruments follow how the life is going (see mood()). It
+// only renders samples, 16-bit mono, so it can run offline as well as from
+// an audio callback.
+use crate::simulation::Stats;
+
+// How the music should sound. A new Mood takes over at the start of the
+// next bar, so changes land on the beat.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Mood {
+    pub tempo: f32, // beats per minute
+    pub root: i32, // MIDI note the key is built on
+    pub minor: bool,
+    pub voices: u32, // 1 (just the melody) to 4 (melody, bass, arpeggio, drums)
+    pub duty: f32, // the melody's pulse width; thinner sounds sadder
+}
+
+static STAGE_ROOTS: [i32; 5] = [60, 62, 57, 55, 53];
+
+// Lonely lives are in a minor key, relaxed ones are slower, and there is
+// more going on the more of life has opened up.
+pub fn mood(stats: &Stats, stage: u32) -> Mood {
+    let relaxation = stats.relaxation.max(0.0).min(1.0);
+    let pride = stats.pride.max(0.0).min(1.0);
+    Mood {
+        tempo: 80.0 + 60.0 * (1.0 - relaxation),
+        root: STAGE_ROOTS[(stage as usize).min(STAGE_ROOTS.len() - 1)],
+        minor: stats.belonging < 0.5,
+        voices: (stage + 1).min(4),
+        duty: 0.125 + 0.375 * pride,
+    }
+}
+
+static MAJOR: [i32; 7] = [0, 2, 4, 5, 7, 9, 11];
+static MINOR: [i32; 7] = [0, 2, 3, 5, 7, 8, 10];
+// scale degree each bar's chord is built on
+static MAJOR_CHORDS: [i32; 4] = [0, 4, 5, 3];
+static MINOR_CHORDS: [i32; 4] = [0, 5, 2, 6];
+// the melody in eighths, as scale degrees above the bar's chord
+static MELODY: [[Option<i32>; 8]; 4] = [
+    [Some(0), None, Some(2), Some(4), Some(2), None, Some(4), Some(7)],
+    [Some(4), None, Some(2), None, Some(0), Some(2), Some(4), None],
+    [Some(2), Some(4), Some(5), None, Some(4), Some(2), Some(0), None],
+    [Some(4), None, Some(2), Some(1), Some(0), None, None, None],
+];
+
+static SIXTEENTHS_PER_BAR: u32 = 16;
+static BARS: u32 = 4;
+
+fn note(mood: &Mood, degree: i32) -> i32 {
+    let scale = if mood.minor { &MINOR } else { &MAJOR };
+    mood.root + 12 * degree.div_euclid(7) + scale[degree.rem_euclid(7) as usize]
+}
+
+fn frequency(note: i32) -> f32 {
+    440.0 * 2f32.powf((note - 69) as f32 / 12.0)
+}
+
+fn pulse(phase: f32, duty: f32) -> f32 {
+    if phase < duty { 1.0 } else { -1.0 }
+}
+
+fn triangle(phase: f32) -> f32 {
+    if phase < 0.5 { 4.0 * phase - 1.0 } else { 3.0 - 4.0 * phase }
+}
+
+pub struct Synth {
+    pub rate: u32, // samples per second
+    pub volume: f32, // 0 to 1
+    mood: Option<Mood>, // None is silence
+    next: Option<Mood>,
+    sixteenth: u32, // into the loop
+    position: u32, // samples into the sixteenth
+    phases: [f32; 3], // melody, bass, arpeggio
+    noise: u32,
+}
+
+impl Synth {
+    pub fn new(rate: u32) -> Self {
+        Self {
+            rate: rate,
+            volume: 1.0,
+            mood: None,
+            next: None,
+            sixteenth: 0,
+            position: 0,
+            phases: [0.0; 3],
+            noise: 1,
+        }
+    }
+
+    // Play mood from the next bar on; None stops the music there.
+    pub fn set_mood(&mut self, mood: Option<Mood>) {
+        if self.mood.is_none() {
+            // nothing is playing, so there's no bar to wait for
+            self.mood = mood;
+            self.sixteenth = 0;
+            self.position = 0;
+        }
+        self.next = mood;
+    }
+
+    fn sixteenth_len(&self, mood: &Mood) -> u32 {
+        ((self.rate as f32 * 60.0 / mood.tempo / 4.0) as u32).max(1)
+    }
+
+    pub fn next_sample(&mut self) -> i16 {
+        let mood = match self.mood {
+            Some(m) => m,
+            None => return 0,
+        };
+        let len = self.sixteenth_len(&mood);
+        let t = self.position as f32 / self.rate as f32; // into the sixteenth
+        let bar = (self.sixteenth / SIXTEENTHS_PER_BAR) as usize;
+        let in_bar = self.sixteenth % SIXTEENTHS_PER_BAR;
+        let chords = if mood.minor { &MINOR_CHORDS } else { &MAJOR_CHORDS };
+        let chord = chords[bar];
+        let mut out = 0.0;
+
+        // melody, in eighths
+        if let Some(degree) = MELODY[bar][(in_bar / 2) as usize] {
+            let f = frequency(note(&mood, chord + degree) + 12);
+            let since = t + (in_bar % 2 * len) as f32 / self.rate as f32;
+            out += pulse(self.phases[0], mood.duty) * 0.25 * (-3.0 * since).exp();
+            self.phases[0] = (self.phases[0] + f / self.rate as f32) % 1.0;
+        }
+        // bass, the chord's root two octaves down
+        if mood.voices >= 2 {
+            let f = frequency(note(&mood, chord) - 24);
+            out += triangle(self.phases[1]) * 0.35;
+            self.phases[1] = (self.phases[1] + f / self.rate as f32) % 1.0;
+        }
+        // arpeggio up and down the chord
+        if mood.voices >= 3 {
+            let step = [0, 2, 4, 2][(in_bar % 4) as usize];
+            let f = frequency(note(&mood, chord + step));
+            out += pulse(self.phases[2], 0.25) * 0.1 * (-12.0 * t).exp();
+            self.phases[2] = (self.phases[2] + f / self.rate as f32) % 1.0;
+        }
+        // drums: noise, long on the beat and short off it
+        if mood.voices >= 4 {
+            self.noise ^= self.noise << 13;
+            self.noise ^= self.noise >> 17;
+            self.noise ^= self.noise << 5;
+            let n = (self.noise % 2) as f32 * 2.0 - 1.0;
+            let decay = if in_bar % 4 == 0 { 30.0 } else if in_bar % 2 == 0 { 120.0 } else { 0.0 };
+            if decay > 0.0 {
+                out += n * 0.15 * (-decay * t).exp();
+            }
+        }
+
+        self.position += 1;
+        if self.position >= len {
+            self.position = 0;
+            self.sixteenth = (self.sixteenth + 1) % (SIXTEENTHS_PER_BAR * BARS);
+            if self.sixteenth % SIXTEENTHS_PER_BAR == 0 {
+                self.mood = self.next;
+            }
+        }
+        (out * self.volume * i16::max_value() as f32) as i16
+    }
+
+    // Fill out with the next samples.
+    pub fn render(&mut self, out: &mut [i16]) {
+        for s in out.iter_mut() {
+            *s = self.next_sample();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A second of music for mood, at 22050Hz.
+    fn second(mood: Option<Mood>) -> Vec<i16> {
+        let mut synth = Synth::new(22050);
+        synth.set_mood(mood);
+        let mut out = vec![0; 22050];
+        synth.render(&mut out);
+        out
+    }
+
+    #[test]
+    fn the_same_mood_renders_the_same() {
+        let mood = mood(&Stats::new(), 4);
+        let out = second(Some(mood));
+        assert!(out == second(Some(mood)));
+        assert!(out.iter().any(|&s| s.abs() > 1000));
+    }
+
+    #[test]
+    fn no_mood_is_silence() {
+        assert!(second(None).iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    fn moods_follow_the_stats() {
+        let mut stats = Stats::new();
+        let happy = mood(&stats, 4);
+        stats.belonging = 0.2;
+        stats.relaxation = 0.0;
+        let lonely = mood(&stats, 4);
+        assert!(!happy.minor && lonely.minor);
+        assert!(lonely.tempo > happy.tempo);
+        assert!(second(Some(happy)) != second(Some(lonely)));
+    }
+}
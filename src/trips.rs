@@ -0,0 +1,148 @@
+// Moves and trips the player makes on their own, rather than when an event
+// offers one: pick a city on the map, then either move there for good or
+// visit it and fly back home. A visit is relaxing and brings you closer to
+// the friends there. Either one costs money, which has to be there to spend,
+// and has to wait out a cooldown before the next. The rates are in assets/rules.toml.
+use crate::simulation::*;
+
+// Start picking a city for action, if old enough to go anywhere alone.
+pub fn plan(sim: &mut Simulation, action: Action) {
+    if sim.age < sim.rules.trips.min_age {
+        return;
+    }
+    let city = step_city(sim, sim.current_city, 1);
+    sim.plan = Some(Plan { action: action, city: city });
+    sim.game_state = GameState::Plan;
+}
+
+// Weeks until another move or trip can be made; 0 when one can.
+pub fn wait_weeks(sim: &Simulation) -> u32 {
+    ((sim.trip_ready - sim.life) / TICKS_PER_WEEK).ceil().max(0.0) as u32
+}
+
+pub fn cost(sim: &Simulation, action: Action) -> f32 {
+    match action {
+        Action::Move => sim.rules.money.flight,
+        Action::Travel => sim.rules.trips.travel,
+    }
+}
+
+// Whether there's the money for action; trips can't be taken on credit.
+pub fn affordable(sim: &Simulation, action: Action) -> bool {
+    sim.stats.money >= cost(sim, action)
+}
+
+// The city dir (-1 or 1) places from city, west to east, skipping home.
+fn step_city(sim: &Simulation, city: usize, dir: i32) -> usize {
+    let mut order: Vec<usize> = (0..sim.cities.len())
+        .filter(|&i| i == city || i != sim.current_city).collect();
+    order.sort_by_key(|&i| (sim.cities[i].position[0], sim.cities[i].position[1]));
+    let at = order.iter().position(|&i| i == city).unwrap() as i32;
+    order[(at + dir).rem_euclid(order.len() as i32) as usize]
+}
+
+// Pick the next (dir 1) or previous (-1) city of the plan.
+pub fn pick(sim: &mut Simulation, dir: i32) {
+    let city = sim.plan.as_ref().unwrap().city;
+    let next = step_city(sim, city, dir);
+    sim.plan.as_mut().unwrap().city = next;
+}
+
+// Carry out the plan, unless it's too soon after the last one or can't be
+// paid for.
+pub fn go(sim: &mut Simulation) {
+    let plan = sim.plan.as_ref().unwrap();
+    let (city, action) = (plan.city, plan.action);
+    if wait_weeks(sim) > 0 || city == sim.current_city || !affordable(sim, action) {
+        return;
+    }
+    let plan = sim.plan.take().unwrap();
+    sim.trip_ready = sim.life + sim.rules.trips.cooldown as f64 * TICKS_PER_WEEK;
+    sim.journeys.push(Journey { age: sim.age, action: plan.action, city: plan.city });
+    take_off(sim);
+    match plan.action {
+        Action::Move => move_home(sim, plan.city),
+        Action::Travel => {
+            sim.stats.money -= sim.rules.trips.travel;
+            sim.trip = Some(plan.city);
+        }
+    }
+}
+
+// Landed in the city being visited; the plane heads home from here.
+pub fn arrive(sim: &mut Simulation, city: usize) {
+    let rules = sim.rules.trips.clone();
+    sim.stats.relaxation += rules.relaxation;
+    for f in sim.cities[city].friends.iter_mut() {
+        f.bond = (f.bond + rules.visit).min(1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::tests::{at_age, city, sim};
+    use crate::simulation::*;
+
+    #[test]
+    fn trip_doesnt_skip_death() {
+        let mut sim = sim(1);
+        at_age(&mut sim, 74.9);
+        sim.stats.money = 1.0;
+        sim.command(Command::Plan(Action::Travel));
+        sim.command(Command::Select(city(&sim, "LA") as i32));
+        sim.command(Command::Confirm);
+        assert!(sim.trip.is_some());
+        for _ in 0..(30.0 / STEP) as u32 {
+            match sim.game_state {
+                GameState::GameOver => break,
+                GameState::Modal => sim.command(Command::Confirm),
+                _ => {}
+            }
+            sim.step(STEP);
+        }
+        assert!(matches!(sim.game_state, GameState::GameOver));
+        assert_eq!(sim.modals_done.last().unwrap().event, "die");
+    }
+
+    #[test]
+    fn cooldown_waits_out_in_game() {
+        let mut sim = sim(1);
+        at_age(&mut sim, 20.0);
+        sim.stats.money = 1.0;
+        sim.command(Command::Plan(Action::Travel));
+        let weeks = sim.rules.trips.cooldown as u32;
+        sim.command(Command::Confirm);
+        // flying there and back, and planning the next, don't count
+        for _ in 0..(20.0 / STEP) as u32 {
+            sim.step(STEP);
+            if let GameState::Game = sim.game_state {
+                sim.command(Command::Plan(Action::Travel));
+            }
+        }
+        assert!(matches!(sim.game_state, GameState::Plan));
+        assert_eq!(super::wait_weeks(&sim), weeks);
+    }
+
+    #[test]
+    fn trips_need_the_money_for_them() {
+        let mut sim = sim(1);
+        at_age(&mut sim, 20.0);
+        for &action in [Action::Travel, Action::Move].iter() {
+            let cost = super::cost(&sim, action);
+            sim.stats.money = cost - 0.01;
+            sim.command(Command::Plan(action));
+            sim.command(Command::Select(city(&sim, "LA") as i32));
+            sim.command(Command::Confirm);
+            assert!(matches!(sim.game_state, GameState::Plan));
+            assert!(sim.journeys.is_empty() && sim.trip.is_none());
+            assert_eq!(sim.stats.money, cost - 0.01);
+            sim.command(Command::Cancel);
+        }
+
+        sim.stats.money = super::cost(&sim, Action::Travel);
+        sim.command(Command::Plan(Action::Travel));
+        sim.command(Command::Confirm);
+        assert!(sim.trip.is_some());
+        assert_eq!(sim.stats.money, 0.0);
+    }
+}